@@ -0,0 +1,340 @@
+use rand::Rng;
+
+use crate::{
+    color::Color,
+    util::{RtcFl, EPSILON},
+};
+
+/// Where in a pixel the sample rays go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SamplePattern {
+    /// Evenly spaced rows and columns. One sample is the pixel's center, as in the book.
+    #[default]
+    Grid,
+    /// One random point in each cell of the grid, which turns aliasing into noise.
+    Jittered,
+    /// The Halton sequence in bases 2 and 3, spread evenly without lining up on a grid.
+    Halton,
+}
+
+impl SamplePattern {
+    /// Positions in the unit square for `samples` rays. Grid and jittered patterns round
+    /// `samples` up to the next square number.
+    pub fn offsets(&self, samples: usize) -> Vec<(RtcFl, RtcFl)> {
+        let side = (samples.max(1) as RtcFl).sqrt().ceil() as usize;
+        let cells = (0..side * side).map(|i| ((i % side) as RtcFl, (i / side) as RtcFl));
+        let side = side as RtcFl;
+
+        match self {
+            SamplePattern::Grid => cells
+                .map(|(x, y)| ((x + 0.5) / side, (y + 0.5) / side))
+                .collect(),
+            SamplePattern::Jittered => {
+                let mut rng = rand::rng();
+                cells
+                    .map(|(x, y)| {
+                        (
+                            (x + rng.random::<RtcFl>()) / side,
+                            (y + rng.random::<RtcFl>()) / side,
+                        )
+                    })
+                    .collect()
+            }
+            // Index 0 of the sequence is the corner, so start at 1
+            SamplePattern::Halton => (1..=samples.max(1))
+                .map(|i| (radical_inverse(i, 2), radical_inverse(i, 3)))
+                .collect(),
+        }
+    }
+}
+
+/// `index` written in `base` and mirrored around the radix point.
+fn radical_inverse(mut index: usize, base: usize) -> RtcFl {
+    let mut result = 0.0;
+    let mut scale = 1.0 / base as RtcFl;
+
+    while index > 0 {
+        result += (index % base) as RtcFl * scale;
+        index /= base;
+        scale /= base as RtcFl;
+    }
+
+    result
+}
+
+/// How the samples around a pixel are weighted into its final color.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Filter {
+    /// A plain average of the samples within the pixel.
+    #[default]
+    Box,
+    /// Weights fall linearly to zero one pixel from the center.
+    Tent,
+    /// A smooth bell, slightly soft.
+    Gaussian,
+    /// Mitchell-Netravali with B = C = 1/3, sharper than a Gaussian at the cost of slight ringing.
+    Mitchell,
+}
+
+impl Filter {
+    /// How far from the pixel's center, in pixels, the filter reaches. Samples are taken over
+    /// this whole footprint, so wider filters blend in some of the neighbouring pixels.
+    pub fn radius(&self) -> RtcFl {
+        match self {
+            Filter::Box => 0.5,
+            Filter::Tent => 1.0,
+            Filter::Gaussian => 1.5,
+            Filter::Mitchell => 2.0,
+        }
+    }
+
+    /// The weight of a sample `x` pixels from the center along one axis.
+    pub fn weight(&self, x: RtcFl) -> RtcFl {
+        let x = x.abs();
+        if x > self.radius() {
+            return 0.0;
+        }
+
+        match self {
+            Filter::Box => 1.0,
+            Filter::Tent => 1.0 - x,
+            Filter::Gaussian => {
+                // Shifted down so the bell reaches zero at the radius instead of being cut off
+                let alpha = 2.0;
+                (-alpha * x * x).exp() - (-alpha * self.radius().powi(2)).exp()
+            }
+            Filter::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                let (x2, x3) = (x * x, x * x * x);
+                if x < 1.0 {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x3
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x2
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                } else {
+                    ((-b - 6.0 * c) * x3
+                        + (6.0 * b + 30.0 * c) * x2
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                }
+            }
+        }
+    }
+}
+
+/// How many rays each pixel gets and how they are combined. The default is the book's single ray
+/// through the pixel's center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AntiAliasing {
+    /// Rays per pixel of area. Filters wider than a pixel keep this density over their whole
+    /// footprint, so they cost more rays.
+    pub samples: usize,
+    pub pattern: SamplePattern,
+    pub filter: Filter,
+}
+
+impl Default for AntiAliasing {
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            pattern: SamplePattern::default(),
+            filter: Filter::default(),
+        }
+    }
+}
+
+impl AntiAliasing {
+    /// Where to send each ray, relative to the pixel's top left corner, and how much it counts.
+    ///
+    /// The pattern covers the filter's whole footprint at `samples` rays per pixel of area, so
+    /// the pixel's own area is sampled as densely as with a box filter.
+    pub fn weighted_offsets(&self) -> Vec<(RtcFl, RtcFl, RtcFl)> {
+        let span = 2.0 * self.filter.radius();
+        let samples = (self.samples.max(1) as RtcFl * span * span).round() as usize;
+
+        self.pattern
+            .offsets(samples)
+            .into_iter()
+            .map(|(u, v)| {
+                let dx = (u - 0.5) * span;
+                let dy = (v - 0.5) * span;
+
+                (
+                    0.5 + dx,
+                    0.5 + dy,
+                    self.filter.weight(dx) * self.filter.weight(dy),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The filtered color of a pixel from `(x, y, weight, color)` samples, as laid out by
+/// `AntiAliasing::weighted_offsets`.
+///
+/// Negative filter lobes can cancel out almost all of the weight, which would blow the color up
+/// or flip its sign. Then the samples inside the pixel are simply averaged instead.
+pub fn combine_samples(samples: &[(RtcFl, RtcFl, RtcFl, Color)]) -> Color {
+    let total_weight: RtcFl = samples.iter().map(|&(_, _, weight, _)| weight).sum();
+
+    if total_weight > EPSILON {
+        let sum = samples
+            .iter()
+            .fold(Color::black(), |sum, &(_, _, weight, color)| {
+                sum + color * weight
+            });
+        return sum * (1.0 / total_weight);
+    }
+
+    let inside = samples
+        .iter()
+        .filter(|&&(x, y, _, _)| (0.0..1.0).contains(&x) && (0.0..1.0).contains(&y))
+        .map(|&(_, _, _, color)| color)
+        .collect::<Vec<_>>();
+    if inside.is_empty() {
+        return Color::black();
+    }
+
+    inside
+        .iter()
+        .fold(Color::black(), |sum, &color| sum + color)
+        * (1.0 / inside.len() as RtcFl)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        antialias::{combine_samples, AntiAliasing, Filter, SamplePattern},
+        color::Color,
+        util::{equal, RtcFl},
+    };
+
+    #[test]
+    fn a_single_grid_sample_is_the_pixel_center() {
+        assert_eq!(SamplePattern::Grid.offsets(1), vec![(0.5, 0.5)]);
+        assert_eq!(
+            AntiAliasing::default().weighted_offsets(),
+            vec![(0.5, 0.5, 1.0)]
+        );
+    }
+
+    #[test]
+    fn grid_samples_are_evenly_spaced() {
+        assert_eq!(
+            SamplePattern::Grid.offsets(4),
+            vec![(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+        );
+        assert_eq!(SamplePattern::Grid.offsets(5).len(), 9);
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cell() {
+        for _ in 0..20 {
+            let offsets = SamplePattern::Jittered.offsets(4);
+
+            assert_eq!(offsets.len(), 4);
+            for (i, (u, v)) in offsets.into_iter().enumerate() {
+                let (cell_u, cell_v) = ((i % 2) as RtcFl * 0.5, (i / 2) as RtcFl * 0.5);
+                assert!((cell_u..=cell_u + 0.5).contains(&u));
+                assert!((cell_v..=cell_v + 0.5).contains(&v));
+            }
+        }
+    }
+
+    #[test]
+    fn halton_samples_follow_the_sequence() {
+        let offsets = SamplePattern::Halton.offsets(3);
+        let expected = [(0.5, 1.0 / 3.0), (0.25, 2.0 / 3.0), (0.75, 1.0 / 9.0)];
+
+        assert_eq!(offsets.len(), 3);
+        for ((u, v), (eu, ev)) in offsets.into_iter().zip(expected) {
+            assert!(equal(u, eu) && equal(v, ev), "got ({}, {})", u, v);
+        }
+    }
+
+    #[test]
+    fn combining_samples_weighs_them_by_the_filter() {
+        let samples = [
+            (0.5, 0.5, 3.0, Color::white()),
+            (1.5, 0.5, 1.0, Color::black()),
+        ];
+
+        assert_eq!(combine_samples(&samples), Color::new(0.75, 0.75, 0.75));
+    }
+
+    #[test]
+    fn cancelled_out_weights_fall_back_to_a_box_filter() {
+        let samples = [
+            (0.25, 0.5, 0.5, Color::new(1.0, 0.0, 0.0)),
+            (0.75, 0.5, 0.5, Color::new(0.0, 0.0, 1.0)),
+            (1.5, 0.5, -1.0, Color::white()),
+        ];
+
+        assert_eq!(combine_samples(&samples), Color::new(0.5, 0.0, 0.5));
+        assert_eq!(combine_samples(&[]), Color::black());
+    }
+
+    #[test]
+    fn filter_weights() {
+        assert_eq!(Filter::Box.weight(0.4), 1.0);
+        assert_eq!(Filter::Box.weight(0.6), 0.0);
+        assert_eq!(Filter::Tent.weight(-0.25), 0.75);
+        assert_eq!(Filter::Tent.weight(1.5), 0.0);
+        assert!(Filter::Gaussian.weight(0.0) > Filter::Gaussian.weight(1.0));
+        assert!(equal(Filter::Gaussian.weight(1.5), 0.0));
+        assert!(equal(Filter::Mitchell.weight(0.0), 8.0 / 9.0));
+        assert!(equal(Filter::Mitchell.weight(1.0), 1.0 / 18.0));
+        assert!(Filter::Mitchell.weight(1.5) < 0.0);
+        assert_eq!(Filter::Mitchell.weight(2.5), 0.0);
+    }
+
+    #[test]
+    fn wide_filters_sample_their_whole_footprint() {
+        let aa = AntiAliasing {
+            samples: 4,
+            filter: Filter::Tent,
+            ..Default::default()
+        };
+        let offsets = aa.weighted_offsets();
+        let inside = |&&(x, y, _): &&(RtcFl, RtcFl, RtcFl)| {
+            (0.0..1.0).contains(&x) && (0.0..1.0).contains(&y)
+        };
+
+        // Two by two pixels of footprint at four samples each
+        assert_eq!(offsets.len(), 16);
+        assert_eq!(
+            offsets.iter().filter(inside).copied().collect::<Vec<_>>(),
+            vec![
+                (0.25, 0.25, 0.5625),
+                (0.75, 0.25, 0.5625),
+                (0.25, 0.75, 0.5625),
+                (0.75, 0.75, 0.5625)
+            ]
+        );
+        assert!(offsets.contains(&(-0.25, -0.25, 0.0625)));
+        assert!(offsets.contains(&(1.25, 0.75, 0.1875)));
+
+        for filter in [
+            Filter::Box,
+            Filter::Tent,
+            Filter::Gaussian,
+            Filter::Mitchell,
+        ] {
+            let aa = AntiAliasing {
+                samples: 16,
+                filter,
+                ..Default::default()
+            };
+            let offsets = aa.weighted_offsets();
+            let reach = offsets
+                .iter()
+                .map(|&(x, _, _)| (x - 0.5).abs())
+                .fold(0.0, RtcFl::max);
+
+            // The pixel itself gets the same sixteen samples whatever the filter
+            assert_eq!(offsets.iter().filter(inside).count(), 16, "{:?}", filter);
+            assert!(reach > filter.radius() - 0.25 && reach < filter.radius());
+        }
+    }
+}
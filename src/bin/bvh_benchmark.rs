@@ -0,0 +1,102 @@
+extern crate rtc_rs as rtc;
+
+use std::{env, time::Instant};
+
+use indicatif::ProgressBar;
+use rtc::{
+    bvh::BvhSplit,
+    camera::Camera,
+    canvas::Canvas,
+    color::Color,
+    light::Light,
+    material::Material,
+    matrix::view_transform,
+    obj::ObjParser,
+    plane::Plane,
+    render::render_parallel,
+    tuples::{point, vector},
+    util::PI,
+    world::World,
+};
+
+// Renders the same mesh with and without a BVH and reports the timings.
+// Pass the path to an OBJ file to use that mesh instead of the generated sphere.
+fn main() {
+    let parser = match env::args().nth(1) {
+        Some(path) => ObjParser::from_file(&path).expect("Unable to read OBJ file."),
+        None => ObjParser::parse(&sphere_mesh(40, 80)),
+    };
+
+    let material = Material {
+        color: Color::new(0.8, 0.3, 0.2),
+        ..Default::default()
+    };
+    let triangles = parser.triangles(&material);
+    println!("Triangles: {}", triangles.len());
+
+    let mut world = World::default();
+    world.add_object(Plane::default());
+    world.add_boxed_objects(triangles);
+    world.light = vec![Light::point(point(-10.0, 10.0, -10.0), Color::white())];
+
+    let mut camera = Camera::new(160, 90, PI / 3.0);
+    camera.set_transform(view_transform(
+        point(0.0, 1.5, -4.0),
+        point(0.0, 1.0, 0.0),
+        vector(0.0, 1.0, 0.0),
+    ));
+
+    world.auto_bvh = None;
+    let brute_force = time_render("No BVH", &camera, &world);
+
+    for split in [BvhSplit::Midpoint, BvhSplit::Sah] {
+        let now = Instant::now();
+        world.build_bvh(split);
+        println!("{:?} BVH built in {:.2?}", split, now.elapsed());
+
+        let (elapsed, canvas) = time_render(&format!("{:?} BVH", split), &camera, &world);
+        println!(
+            "  speedup: {:.1}x, same image: {}",
+            brute_force.0.as_secs_f64() / elapsed.as_secs_f64(),
+            canvas.rgba_bytes() == brute_force.1.rgba_bytes()
+        );
+    }
+}
+
+fn time_render(label: &str, camera: &Camera, world: &World) -> (std::time::Duration, Canvas) {
+    let now = Instant::now();
+    let canvas = render_parallel(camera, world, &ProgressBar::hidden(), false);
+    let elapsed = now.elapsed();
+    println!("{}: rendered in {:.2?}", label, elapsed);
+
+    (elapsed, canvas)
+}
+
+/// A unit sphere resting on the floor, as OBJ text with `rings * segments` quads.
+fn sphere_mesh(rings: usize, segments: usize) -> String {
+    let mut obj = String::new();
+
+    for r in 0..=rings {
+        let theta = PI * r as f32 / rings as f32;
+        for s in 0..segments {
+            let phi = 2.0 * PI * s as f32 / segments as f32;
+            obj += &format!(
+                "v {} {} {}\n",
+                theta.sin() * phi.cos(),
+                theta.cos() + 1.0,
+                theta.sin() * phi.sin()
+            );
+        }
+    }
+
+    for r in 0..rings {
+        for s in 0..segments {
+            // OBJ indices start at 1
+            let a = r * segments + s + 1;
+            let b = r * segments + (s + 1) % segments + 1;
+            obj += &format!("f {} {} {} {}\n", a, b, b + segments, a + segments);
+        }
+    }
+
+    obj
+}
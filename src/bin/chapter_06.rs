@@ -1,19 +1,15 @@
 extern crate rtc_rs as rtc;
 
-use std::{
-    fs::File,
-    io::{BufWriter, Write},
-    time::{Duration, Instant},
-};
+use std::time::{Duration, Instant};
 
 use rtc::{
-    canvas::Canvas,
+    canvas::{BitDepth, Canvas, PngOptions},
     color::Color,
+    intersections::Intersections,
     light::{lighting, Light},
     ray::Ray,
     shape::Shape,
     sphere::Sphere,
-    intersections::Intersections,
     tuples::point,
     util::RtcFl,
 };
@@ -60,7 +56,7 @@ fn main() {
                         &hit_point,
                         &eye_vector,
                         normal_vector,
-                        false,
+                        1.0,
                     );
                     canvas.write_pixel(x, y, color);
                 }
@@ -78,19 +74,19 @@ fn main() {
     println!("Elapsed time for rendering: {:.2?}", elapsed);
 
     // Write to PPM file
-    let mut ppm_file = File::create("chapter_06.ppm").expect("Unable to create file.");
-
-    ppm_file
-        .write_all(canvas.to_ppm().as_bytes())
+    canvas
+        .save_ppm("chapter_06.ppm", BitDepth::Eight)
         .expect("Unable to write file.");
 
     // Write to PNG file
-    let png_file = File::create("rendered/chapter_06.png").expect("Unable to create file.");
-    let w = &mut BufWriter::new(png_file);
-    let mut encoder = png::Encoder::new(w, canvas.width as u32, canvas.height as u32);
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
-
-    let mut writer = encoder.write_header().unwrap();
-    writer.write_image_data(&canvas.to_png()).unwrap();
+    canvas
+        .save_png(
+            "rendered/chapter_06.png",
+            &PngOptions {
+                render_time: Some(elapsed),
+                scene: Some("Chapter 6".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect("Unable to write file.");
 }
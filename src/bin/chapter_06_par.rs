@@ -1,21 +1,19 @@
 extern crate rtc_rs as rtc;
 
 use std::{
-    fs::File,
-    io::BufWriter,
     sync::mpsc,
     thread::{self, available_parallelism},
     time::{Duration, Instant},
 };
 
 use rtc::{
-    canvas::Canvas,
+    canvas::{Canvas, PngOptions},
     color::Color,
+    intersections::Intersections,
     light::{lighting, Light},
     ray::Ray,
     shape::Shape,
     sphere::Sphere,
-    intersections::Intersections,
     tuples::point,
     util::RtcFl,
 };
@@ -92,7 +90,7 @@ fn main() {
                         let hit_point = r.position(the_hit.t);
                         let normal_vector = &the_hit.shape.normal_at(hit_point);
                         let eye_vector = -r.direction;
-                        
+
                         lighting(
                             &cloned_shape.material,
                             &cloned_shape,
@@ -100,12 +98,12 @@ fn main() {
                             &hit_point,
                             &eye_vector,
                             normal_vector,
-                            false,
+                            1.0,
                         )
                     }
 
                     // Otherwise, return a black pixel
-                    None => Color::black()
+                    None => Color::black(),
                 };
 
                 // Send pixel coordinates and color to the main thread
@@ -146,12 +144,14 @@ fn main() {
     // Write to PNG file
     let path = "rendered/chapter_06_par.png";
     println!("Writing to file '{}'...", &path);
-    let png_file = File::create(path).expect("Unable to create file.");
-    let w = &mut BufWriter::new(png_file);
-    let mut encoder = png::Encoder::new(w, canvas.width as u32, canvas.height as u32);
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
-
-    let mut writer = encoder.write_header().unwrap();
-    writer.write_image_data(&canvas.to_png()).unwrap();
+    canvas
+        .save_png(
+            path,
+            &PngOptions {
+                render_time: Some(elapsed),
+                scene: Some("Chapter 6 (parallel)".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect("Unable to write file.");
 }
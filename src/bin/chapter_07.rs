@@ -1,16 +1,14 @@
 extern crate rtc_rs as rtc;
 
-use std::{
-    fs::File,
-    io::BufWriter,
-    time::{Duration, Instant},
-};
+use std::time::{Duration, Instant};
 
 use indicatif::ProgressBar;
 use rtc::{
     camera::Camera,
+    canvas::PngOptions,
     color::Color,
     light::Light,
+    material::Material,
     matrix::view_transform,
     render::render_parallel,
     sphere::Sphere,
@@ -33,58 +31,50 @@ fn main() {
     println!("Image size: {}x{}", canvas_pixels, canvas_pixels);
 
     // Floor
-    let mut floor = Sphere {
-        transform: scaling(10.0, 0.01, 10.0),
-        ..Default::default()
-    };
+    let mut floor = Sphere::new(scaling(10.0, 0.01, 10.0), Material::default());
 
     floor.material.color = Color::new(1.0, 0.9, 0.9);
     floor.material.specular = 0.0;
 
     // Left wall
-    let left_wall = Sphere {
-        transform: translation(0.0, 0.0, 5.0)
+    let left_wall = Sphere::new(
+        translation(0.0, 0.0, 5.0)
             * rotation_y(-PI / 4.0)
             * rotation_z(PI / 2.0)
             * scaling(10.0, 0.01, 10.0),
-        material: floor.material.clone(),
-        ..Default::default()
-    };
+        floor.material.clone(),
+    );
 
     // Right wall
-    let right_wall = Sphere {
-        transform: translation(0.0, 0.0, 5.0)
+    let right_wall = Sphere::new(
+        translation(0.0, 0.0, 5.0)
             * rotation_y(PI / 4.0)
             * rotation_z(PI / 2.0)
             * scaling(10.0, 0.01, 10.0),
-        material: floor.material.clone(),
-        ..Default::default()
-    };
+        floor.material.clone(),
+    );
 
     // Middle sphere
-    let mut middle = Sphere {
-        transform: translation(-0.5, 1.0, 0.5),
-        ..Default::default()
-    };
+    let mut middle = Sphere::new(translation(-0.5, 1.0, 0.5), Material::default());
 
     middle.material.color = Color::random();
     middle.material.diffuse = 0.7;
     middle.material.specular = 0.3;
 
     // Right sphere
-    let mut right = Sphere {
-        transform: translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5),
-        ..Default::default()
-    };
+    let mut right = Sphere::new(
+        translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5),
+        Material::default(),
+    );
     right.material.color = Color::random();
     right.material.diffuse = 0.7;
     right.material.specular = 0.3;
 
     // Left sphere
-    let mut left = Sphere {
-        transform: translation(-1.5, 0.33, -0.75) * scaling(0.33, 0.33, 0.33),
-        ..Default::default()
-    };
+    let mut left = Sphere::new(
+        translation(-1.5, 0.33, -0.75) * scaling(0.33, 0.33, 0.33),
+        Material::default(),
+    );
 
     left.material.color = Color::random();
     left.material.diffuse = 0.7;
@@ -105,11 +95,11 @@ fn main() {
     world.light = vec![Light::point(point(-10.0, 10.0, -10.0), Color::white())];
 
     let mut camera = Camera::new(canvas_pixels, canvas_pixels, PI / 3.0);
-    camera.transform = view_transform(
+    camera.set_transform(view_transform(
         point(0.0, 1.5, -5.0),
         point(0.0, 1.0, 0.0),
         vector(0.0, 1.0, 0.0),
-    );
+    ));
 
     let bar = ProgressBar::new((canvas_pixels * canvas_pixels) as u64);
     bar.enable_steady_tick(Duration::from_millis(250));
@@ -135,14 +125,16 @@ fn main() {
     // Write to PNG file
     let path = "rendered/chapter_07.png";
     println!("Writing to file '{}'...", &path);
-    let png_file = File::create(path).expect("Unable to create file.");
-    let w = &mut BufWriter::new(png_file);
-    let mut encoder = png::Encoder::new(w, canvas.width as u32, canvas.height as u32);
-    encoder.set_color(png::ColorType::Rgba);
-    encoder.set_depth(png::BitDepth::Eight);
-
-    let mut writer = encoder.write_header().unwrap();
-    writer.write_image_data(&canvas.to_png()).unwrap();
+    canvas
+        .save_png(
+            path,
+            &PngOptions {
+                render_time: Some(elapsed),
+                scene: Some("Chapter 7".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect("Unable to write file.");
 
     let elapsed = now.elapsed();
     println!("Elapsed time for saving file: {:.2?}", elapsed);
@@ -12,7 +12,7 @@ use rtc::{
     color::Color,
     light::Light,
     material::Material,
-    matrix::view_transform,
+    matrix::{view_transform, Matrix4},
     plane::Plane,
     render::render_parallel_incremental,
     sphere::Sphere,
@@ -246,34 +246,31 @@ impl App for RayGuiApp {
 
 fn build_scene(image_width: usize, image_height: usize) -> (Camera, World) {
     // Floor
-    let floor = Plane {
-        material: Material::new(Color::random(), None, 0.1, 1.0, 1.0, 1.0),
-        ..Default::default()
-    };
+    let floor = Plane::new(
+        Matrix4::identity(),
+        Material::new(Color::random(), None, 0.1, 1.0, 1.0, 1.0),
+    );
 
     // Middle sphere
-    let mut middle = Sphere {
-        transform: translation(-0.5, 1.0, 0.5),
-        ..Default::default()
-    };
+    let mut middle = Sphere::new(translation(-0.5, 1.0, 0.5), Material::default());
     middle.material.color = Color::random();
     middle.material.diffuse = 0.7;
     middle.material.specular = 0.3;
 
     // Right sphere
-    let mut right = Sphere {
-        transform: translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5),
-        ..Default::default()
-    };
+    let mut right = Sphere::new(
+        translation(1.5, 0.5, -0.5) * scaling(0.5, 0.5, 0.5),
+        Material::default(),
+    );
     right.material.color = Color::random();
     right.material.diffuse = 0.7;
     right.material.specular = 0.3;
 
     // Left sphere
-    let mut left = Sphere {
-        transform: translation(-1.5, 0.33, -0.75) * scaling(0.33, 0.33, 0.33),
-        ..Default::default()
-    };
+    let mut left = Sphere::new(
+        translation(-1.5, 0.33, -0.75) * scaling(0.33, 0.33, 0.33),
+        Material::default(),
+    );
     left.material.color = Color::random();
     left.material.diffuse = 0.7;
     left.material.specular = 0.3;
@@ -290,11 +287,11 @@ fn build_scene(image_width: usize, image_height: usize) -> (Camera, World) {
     world.light = vec![Light::point(point(-10.0, 10.0, -10.0), Color::white())];
 
     let mut camera = Camera::new(image_width, image_height, PI / 3.0);
-    camera.transform = view_transform(
+    camera.set_transform(view_transform(
         point(0.0, 1.5, -5.0),
         point(0.0, 1.0, 0.0),
         vector(0.0, 1.0, 0.0),
-    );
+    ));
 
     (camera, world)
 }
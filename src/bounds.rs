@@ -0,0 +1,328 @@
+use crate::{
+    matrix::Matrix4,
+    ray::Ray,
+    tuples::{point, Tuple},
+    util::{RtcFl, EPSILON},
+};
+
+/// An axis-aligned box, used to skip shapes a ray cannot possibly hit.
+///
+/// An empty box has its minimum at +infinity and its maximum at -infinity, so adding the
+/// first point or box to it always works out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Tuple,
+    pub max: Tuple,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl BoundingBox {
+    pub fn new(min: Tuple, max: Tuple) -> Self {
+        Self { min, max }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            min: point(RtcFl::INFINITY, RtcFl::INFINITY, RtcFl::INFINITY),
+            max: point(-RtcFl::INFINITY, -RtcFl::INFINITY, -RtcFl::INFINITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn add_point(&mut self, p: Tuple) {
+        self.min = point(
+            self.min.x.min(p.x),
+            self.min.y.min(p.y),
+            self.min.z.min(p.z),
+        );
+        self.max = point(
+            self.max.x.max(p.x),
+            self.max.y.max(p.y),
+            self.max.z.max(p.z),
+        );
+    }
+
+    /// Grows this box to also cover `other`.
+    pub fn merge(&mut self, other: &BoundingBox) {
+        if other.is_empty() {
+            return;
+        }
+        self.add_point(other.min);
+        self.add_point(other.max);
+    }
+
+    pub fn contains_point(&self, p: Tuple) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x)
+            && (self.min.y..=self.max.y).contains(&p.y)
+            && (self.min.z..=self.max.z).contains(&p.z)
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    pub fn center(&self) -> Tuple {
+        point(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
+
+    pub fn surface_area(&self) -> RtcFl {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// The box that covers this one after transforming it by `m`.
+    ///
+    /// Works one matrix entry at a time rather than transforming the eight corners, so
+    /// that the infinite extents of planes and untruncated cylinders don't turn into NaN
+    /// when multiplied by a zero.
+    pub fn transform(&self, m: &Matrix4) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let old_min = [self.min.x, self.min.y, self.min.z];
+        let old_max = [self.max.x, self.max.y, self.max.z];
+        let mut new_min = [m[(0, 3)], m[(1, 3)], m[(2, 3)]];
+        let mut new_max = new_min;
+
+        for i in 0..3 {
+            for j in 0..3 {
+                let a = m[(i, j)];
+                if a == 0.0 {
+                    continue;
+                }
+                let e = a * old_min[j];
+                let f = a * old_max[j];
+                new_min[i] += e.min(f);
+                new_max[i] += e.max(f);
+            }
+        }
+
+        BoundingBox::new(
+            point(new_min[0], new_min[1], new_min[2]),
+            point(new_max[0], new_max[1], new_max[2]),
+        )
+    }
+
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        tmin <= tmax && tmax >= 0.0
+    }
+}
+
+/// Finds where a ray enters and leaves the slab between `min` and `max` on a single axis.
+pub(crate) fn check_axis(
+    origin: RtcFl,
+    direction: RtcFl,
+    min: RtcFl,
+    max: RtcFl,
+) -> (RtcFl, RtcFl) {
+    // A ray parallel to the slab either runs inside it forever or never touches it
+    if direction.abs() < EPSILON {
+        return if (min..=max).contains(&origin) {
+            (-RtcFl::INFINITY, RtcFl::INFINITY)
+        } else {
+            (RtcFl::INFINITY, -RtcFl::INFINITY)
+        };
+    }
+
+    let tmin = (min - origin) / direction;
+    let tmax = (max - origin) / direction;
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::SQRT_2;
+
+    use crate::{
+        bounds::BoundingBox,
+        ray::Ray,
+        transformation::{rotation_x, rotation_y, translation},
+        tuples::{point, vector},
+        util::{RtcFl, PI},
+    };
+
+    #[test]
+    fn creating_an_empty_bounding_box() {
+        let b = BoundingBox::empty();
+
+        assert!(b.is_empty());
+        assert_eq!(b.min.x, RtcFl::INFINITY);
+        assert_eq!(b.max.x, -RtcFl::INFINITY);
+    }
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let mut b = BoundingBox::empty();
+        b.add_point(point(-5.0, 2.0, 0.0));
+        b.add_point(point(7.0, 0.0, -3.0));
+
+        assert_eq!(b.min, point(-5.0, 0.0, -3.0));
+        assert_eq!(b.max, point(7.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn merging_one_bounding_box_into_another() {
+        let mut b1 = BoundingBox::new(point(-5.0, -2.0, 0.0), point(7.0, 4.0, 4.0));
+        let b2 = BoundingBox::new(point(8.0, -7.0, -2.0), point(14.0, 2.0, 8.0));
+
+        b1.merge(&b2);
+
+        assert_eq!(b1.min, point(-5.0, -7.0, -2.0));
+        assert_eq!(b1.max, point(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_point() {
+        let b = BoundingBox::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+        let examples = [
+            (point(5.0, -2.0, 0.0), true),
+            (point(11.0, 4.0, 7.0), true),
+            (point(8.0, 1.0, 3.0), true),
+            (point(3.0, 0.0, 3.0), false),
+            (point(8.0, -4.0, 3.0), false),
+            (point(8.0, 1.0, -1.0), false),
+            (point(13.0, 1.0, 3.0), false),
+            (point(8.0, 5.0, 3.0), false),
+            (point(8.0, 1.0, 8.0), false),
+        ];
+
+        for (p, result) in examples {
+            assert_eq!(b.contains_point(p), result);
+        }
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_box() {
+        let b = BoundingBox::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+        let examples = [
+            (point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0), true),
+            (point(6.0, -1.0, 1.0), point(10.0, 3.0, 6.0), true),
+            (point(4.0, -3.0, -1.0), point(10.0, 3.0, 6.0), false),
+            (point(6.0, -1.0, 1.0), point(12.0, 5.0, 8.0), false),
+        ];
+
+        for (min, max, result) in examples {
+            assert_eq!(b.contains_box(&BoundingBox::new(min, max)), result);
+        }
+    }
+
+    #[test]
+    fn surface_area_of_a_bounding_box() {
+        let b = BoundingBox::new(point(-1.0, 0.0, 2.0), point(1.0, 3.0, 6.0));
+
+        assert_eq!(b.surface_area(), 52.0);
+        assert_eq!(BoundingBox::empty().surface_area(), 0.0);
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+        let m = rotation_x(PI / 4.0) * rotation_y(PI / 4.0);
+
+        let b2 = b.transform(&m);
+
+        assert_eq!(b2.min, point(-SQRT_2, -1.70711, -1.70711));
+        assert_eq!(b2.max, point(SQRT_2, 1.70711, 1.70711));
+    }
+
+    #[test]
+    fn transforming_an_infinite_bounding_box() {
+        let b = BoundingBox::new(
+            point(-RtcFl::INFINITY, 0.0, -RtcFl::INFINITY),
+            point(RtcFl::INFINITY, 0.0, RtcFl::INFINITY),
+        );
+
+        let b2 = b.transform(&translation(0.0, 2.0, 0.0));
+
+        assert_eq!(b2.min.x, -RtcFl::INFINITY);
+        assert_eq!(b2.min.y, 2.0);
+        assert_eq!(b2.max.y, 2.0);
+        assert_eq!(b2.max.z, RtcFl::INFINITY);
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_bounding_box_at_the_origin() {
+        let b = BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+        let examples = [
+            (point(5.0, 0.5, 0.0), vector(-1.0, 0.0, 0.0), true),
+            (point(-5.0, 0.5, 0.0), vector(1.0, 0.0, 0.0), true),
+            (point(0.5, 5.0, 0.0), vector(0.0, -1.0, 0.0), true),
+            (point(0.5, -5.0, 0.0), vector(0.0, 1.0, 0.0), true),
+            (point(0.5, 0.0, 5.0), vector(0.0, 0.0, -1.0), true),
+            (point(0.5, 0.0, -5.0), vector(0.0, 0.0, 1.0), true),
+            (point(0.0, 0.5, 0.0), vector(0.0, 0.0, 1.0), true),
+            (point(-2.0, 0.0, 0.0), vector(2.0, 4.0, 6.0), false),
+            (point(0.0, -2.0, 0.0), vector(6.0, 2.0, 4.0), false),
+            (point(0.0, 0.0, -2.0), vector(4.0, 6.0, 2.0), false),
+            (point(2.0, 0.0, 2.0), vector(0.0, 0.0, -1.0), false),
+            (point(0.0, 2.0, 2.0), vector(0.0, -1.0, 0.0), false),
+            (point(2.0, 2.0, 0.0), vector(-1.0, 0.0, 0.0), false),
+        ];
+
+        for (origin, direction, result) in examples {
+            let r = Ray::new(&origin, &direction.normalize());
+            assert_eq!(b.intersects(&r), result);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_non_cubic_bounding_box() {
+        let b = BoundingBox::new(point(5.0, -2.0, 0.0), point(11.0, 4.0, 7.0));
+        let examples = [
+            (point(15.0, 1.0, 2.0), vector(-1.0, 0.0, 0.0), true),
+            (point(-5.0, -1.0, 4.0), vector(1.0, 0.0, 0.0), true),
+            (point(7.0, 6.0, 5.0), vector(0.0, -1.0, 0.0), true),
+            (point(9.0, -5.0, 6.0), vector(0.0, 1.0, 0.0), true),
+            (point(8.0, 2.0, 12.0), vector(0.0, 0.0, -1.0), true),
+            (point(6.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), true),
+            (point(8.0, 1.0, 3.5), vector(0.0, 0.0, 1.0), true),
+            (point(9.0, -1.0, -8.0), vector(2.0, 4.0, 6.0), false),
+            (point(8.0, 3.0, -4.0), vector(6.0, 2.0, 4.0), false),
+            (point(9.0, -1.0, -2.0), vector(4.0, 6.0, 2.0), false),
+            (point(4.0, 0.0, 9.0), vector(0.0, 0.0, -1.0), false),
+            (point(8.0, 6.0, -1.0), vector(0.0, -1.0, 0.0), false),
+            (point(12.0, 5.0, 4.0), vector(-1.0, 0.0, 0.0), false),
+        ];
+
+        for (origin, direction, result) in examples {
+            let r = Ray::new(&origin, &direction.normalize());
+            assert_eq!(b.intersects(&r), result);
+        }
+    }
+
+    #[test]
+    fn a_box_behind_the_ray_is_not_intersected() {
+        let b = BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0));
+        let r = Ray::new(&point(0.0, 0.0, 5.0), &vector(0.0, 0.0, 1.0));
+
+        assert!(!b.intersects(&r));
+    }
+}
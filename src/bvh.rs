@@ -0,0 +1,384 @@
+use crate::{
+    bounds::BoundingBox, intersections::Intersection, ray::Ray, shape::Shape, tuples::Tuple,
+    util::RtcFl,
+};
+
+/// Nodes with this many shapes or fewer are never split further.
+const LEAF_SIZE: usize = 4;
+
+/// Cost of visiting a node relative to intersecting a single shape, used by the SAH build.
+const TRAVERSAL_COST: RtcFl = 0.5;
+
+/// How a node's shapes are divided between its two children.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BvhSplit {
+    /// Split the longest axis in the middle. Fast to build and good enough for most scenes.
+    #[default]
+    Midpoint,
+    /// Pick the split with the lowest surface area heuristic cost. Slower to build, but
+    /// produces tighter trees for uneven geometry like meshes.
+    Sah,
+}
+
+#[derive(Debug, Clone)]
+enum BvhNode {
+    Leaf {
+        bounds: BoundingBox,
+        shapes: Vec<usize>,
+    },
+    Interior {
+        bounds: BoundingBox,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl BvhNode {
+    fn bounds(&self) -> &BoundingBox {
+        match self {
+            BvhNode::Leaf { bounds, .. } | BvhNode::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// A bounding volume hierarchy over a slice of shapes.
+///
+/// The tree only stores indices into the slice it was built from, so the same slice has to
+/// be passed back in when traversing it. Shapes without finite bounds, like planes, can't be
+/// placed in the tree and are tested against every ray.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    unbounded: Vec<usize>,
+    len: usize,
+}
+
+impl Bvh {
+    pub fn build(shapes: &[Box<dyn Shape>], split: BvhSplit) -> Self {
+        let mut bvh = Self {
+            len: shapes.len(),
+            ..Default::default()
+        };
+
+        let mut items = vec![];
+        for (i, s) in shapes.iter().enumerate() {
+            let b = s.parent_space_bounds();
+            if b.is_empty() || !is_finite(&b) {
+                bvh.unbounded.push(i);
+            } else {
+                items.push((i, b));
+            }
+        }
+
+        if !items.is_empty() {
+            bvh.build_node(items, split);
+        }
+
+        bvh
+    }
+
+    /// The number of shapes the tree was built over.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds a node for `items` and everything below it, returning the node's index.
+    fn build_node(&mut self, mut items: Vec<(usize, BoundingBox)>, split: BvhSplit) -> usize {
+        let mut bounds = BoundingBox::empty();
+        for (_, b) in items.iter() {
+            bounds.merge(b);
+        }
+
+        let index = self.nodes.len();
+        if items.len() <= LEAF_SIZE {
+            self.push_leaf(bounds, &items);
+            return index;
+        }
+
+        let split_at = match split {
+            BvhSplit::Midpoint => split_midpoint(&mut items),
+            BvhSplit::Sah => split_sah(&mut items, &bounds),
+        };
+
+        let Some(split_at) = split_at else {
+            self.push_leaf(bounds, &items);
+            return index;
+        };
+
+        // Reserve this node's slot, then fill it in once the children know their indices
+        self.push_leaf(bounds, &[]);
+        let right_items = items.split_off(split_at);
+        let left = self.build_node(items, split);
+        let right = self.build_node(right_items, split);
+        self.nodes[index] = BvhNode::Interior {
+            bounds,
+            left,
+            right,
+        };
+
+        index
+    }
+
+    fn push_leaf(&mut self, bounds: BoundingBox, items: &[(usize, BoundingBox)]) {
+        self.nodes.push(BvhNode::Leaf {
+            bounds,
+            shapes: items.iter().map(|(i, _)| *i).collect(),
+        });
+    }
+
+    /// All intersections between the ray and `shapes`, skipping every shape whose bounding
+    /// box the ray misses.
+    pub fn intersect<'s>(&self, shapes: &'s [Box<dyn Shape>], ray: &Ray) -> Vec<Intersection<'s>> {
+        let mut xs = vec![];
+        self.visit(ray, |i| {
+            xs.extend(shapes[i].intersect(ray));
+            false
+        });
+        xs
+    }
+
+    /// Whether the ray hits anything in `shapes` at a distance between 0 and `max_t`.
+    ///
+    /// Stops at the first such hit rather than collecting every intersection.
+    pub fn hit_before(&self, shapes: &[Box<dyn Shape>], ray: &Ray, max_t: RtcFl) -> bool {
+        self.visit(ray, |i| {
+            shapes[i]
+                .intersect(ray)
+                .iter()
+                .any(|x| x.t >= 0.0 && x.t < max_t)
+        })
+    }
+
+    /// Calls `f` with the index of every shape the ray might hit, until it returns true.
+    fn visit(&self, ray: &Ray, mut f: impl FnMut(usize) -> bool) -> bool {
+        if self.unbounded.iter().any(|&i| f(i)) {
+            return true;
+        }
+
+        let mut stack = vec![];
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if !node.bounds().intersects(ray) {
+                continue;
+            }
+
+            match node {
+                BvhNode::Leaf { shapes, .. } => {
+                    if shapes.iter().any(|&i| f(i)) {
+                        return true;
+                    }
+                }
+                BvhNode::Interior { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+fn is_finite(b: &BoundingBox) -> bool {
+    [b.min.x, b.min.y, b.min.z, b.max.x, b.max.y, b.max.z]
+        .iter()
+        .all(|c| c.is_finite())
+}
+
+fn axis(t: Tuple, axis: usize) -> RtcFl {
+    match axis {
+        0 => t.x,
+        1 => t.y,
+        _ => t.z,
+    }
+}
+
+fn centroid_bounds(items: &[(usize, BoundingBox)]) -> BoundingBox {
+    let mut b = BoundingBox::empty();
+    for (_, item) in items.iter() {
+        b.add_point(item.center());
+    }
+    b
+}
+
+fn sort_along(items: &mut [(usize, BoundingBox)], a: usize) {
+    items.sort_unstable_by(|(_, l), (_, r)| {
+        axis(l.center(), a)
+            .partial_cmp(&axis(r.center(), a))
+            .expect("Unable to sort bounding boxes!")
+    });
+}
+
+/// Sorts the items along the widest axis of their centres and returns where to split them.
+///
+/// Splits in the middle of that axis, or in the middle of the list if every item would end
+/// up on the same side. Returns `None` if all centres coincide.
+fn split_midpoint(items: &mut [(usize, BoundingBox)]) -> Option<usize> {
+    let centroids = centroid_bounds(items);
+    let extent = centroids.max - centroids.min;
+    let a = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+
+    if axis(extent, a) <= 0.0 {
+        return None;
+    }
+
+    sort_along(items, a);
+    let mid = axis(centroids.center(), a);
+    let split_at = items.partition_point(|(_, b)| axis(b.center(), a) < mid);
+
+    if split_at == 0 || split_at == items.len() {
+        Some(items.len() / 2)
+    } else {
+        Some(split_at)
+    }
+}
+
+/// Tries every split along every axis and keeps the one with the lowest surface area
+/// heuristic cost, leaving the items sorted along that axis.
+///
+/// Returns `None` if keeping the items together in a leaf is cheaper than any split.
+fn split_sah(items: &mut [(usize, BoundingBox)], bounds: &BoundingBox) -> Option<usize> {
+    let n = items.len();
+    let parent_area = bounds.surface_area();
+    if parent_area <= 0.0 {
+        return split_midpoint(items);
+    }
+    let mut best: Option<(RtcFl, usize, usize)> = None;
+
+    for a in 0..3 {
+        sort_along(items, a);
+
+        // Areas of the boxes around the first i items, and around the last n - i items
+        let mut left_areas = vec![0.0; n];
+        let mut b = BoundingBox::empty();
+        for i in 1..n {
+            b.merge(&items[i - 1].1);
+            left_areas[i] = b.surface_area();
+        }
+
+        let mut b = BoundingBox::empty();
+        for i in (1..n).rev() {
+            b.merge(&items[i].1);
+            let cost = TRAVERSAL_COST
+                + (left_areas[i] * i as RtcFl + b.surface_area() * (n - i) as RtcFl) / parent_area;
+
+            if best.is_none_or(|(c, _, _)| cost < c) {
+                best = Some((cost, a, i));
+            }
+        }
+    }
+
+    let (cost, a, split_at) = best?;
+    if cost >= n as RtcFl {
+        return None;
+    }
+
+    sort_along(items, a);
+    Some(split_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bvh::{Bvh, BvhSplit},
+        material::Material,
+        plane::Plane,
+        ray::Ray,
+        shape::Shape,
+        sphere::Sphere,
+        transformation::translation,
+        tuples::{point, vector},
+    };
+
+    fn row_of_spheres(count: usize) -> Vec<Box<dyn Shape>> {
+        (0..count)
+            .map(|i| {
+                Box::new(Sphere::new(
+                    translation(i as f32 * 3.0, 0.0, 0.0),
+                    Material::default(),
+                )) as Box<dyn Shape>
+            })
+            .collect()
+    }
+
+    #[test]
+    fn building_a_bvh_over_no_shapes() {
+        let bvh = Bvh::build(&[], BvhSplit::Midpoint);
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        assert!(bvh.is_empty());
+        assert!(bvh.intersect(&[], &r).is_empty());
+    }
+
+    #[test]
+    fn a_bvh_finds_the_same_intersections_as_testing_every_shape() {
+        let shapes = row_of_spheres(50);
+
+        for split in [BvhSplit::Midpoint, BvhSplit::Sah] {
+            let bvh = Bvh::build(&shapes, split);
+            assert_eq!(bvh.len(), 50);
+
+            for i in 0..50 {
+                let r = Ray::new(
+                    &point(i as f32 * 3.0 + 0.5, 0.0, -5.0),
+                    &vector(0.0, 0.0, 1.0),
+                );
+
+                let xs = bvh.intersect(&shapes, &r);
+
+                assert_eq!(xs.len(), 2);
+                assert_eq!(xs[0].shape.id(), shapes[i].id());
+            }
+        }
+    }
+
+    #[test]
+    fn a_ray_between_the_shapes_hits_nothing() {
+        let shapes = row_of_spheres(20);
+        let bvh = Bvh::build(&shapes, BvhSplit::Sah);
+        let r = Ray::new(&point(1.5, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        assert!(bvh.intersect(&shapes, &r).is_empty());
+        assert!(!bvh.hit_before(&shapes, &r, 100.0));
+    }
+
+    #[test]
+    fn shapes_without_finite_bounds_are_always_tested() {
+        let mut shapes = row_of_spheres(10);
+        shapes.push(Box::new(Plane::new(
+            translation(0.0, -1.0, 0.0),
+            Material::default(),
+        )));
+        let bvh = Bvh::build(&shapes, BvhSplit::Midpoint);
+        let r = Ray::new(&point(100.0, 5.0, 0.0), &vector(0.0, -1.0, 0.0));
+
+        let xs = bvh.intersect(&shapes, &r);
+
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 6.0);
+    }
+
+    #[test]
+    fn hit_before_ignores_hits_beyond_the_limit() {
+        let shapes = row_of_spheres(10);
+        let bvh = Bvh::build(&shapes, BvhSplit::Midpoint);
+        let r = Ray::new(&point(-5.0, 0.0, 0.0), &vector(1.0, 0.0, 0.0));
+
+        assert!(bvh.hit_before(&shapes, &r, 4.5));
+        assert!(!bvh.hit_before(&shapes, &r, 3.5));
+    }
+}
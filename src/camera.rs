@@ -1,6 +1,8 @@
 use crate::{
-    matrix::Matrix4,
+    antialias::AntiAliasing,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
     ray::Ray,
+    tonemap::ToneMapping,
     tuples::point,
     util::RtcFl,
 };
@@ -10,16 +12,18 @@ pub struct Camera {
     pub hsize: usize,
     pub vsize: usize,
     pub fov: RtcFl,
-    pub transform: Matrix4,
+    transform: CachedTransform,
     pub pixel_size: RtcFl,
     pub half_height: RtcFl,
     pub half_width: RtcFl,
+    /// Applied to the canvases this camera renders
+    pub tone_mapping: ToneMapping,
+    /// How many rays go through each pixel and how they are combined
+    pub antialiasing: AntiAliasing,
 }
 
 impl Camera {
     pub fn new(hsize: usize, vsize: usize, fov: RtcFl) -> Self {
-        let transform = Matrix4::identity();
-
         let half_view = (fov / 2.0).tan();
         let aspect = hsize as RtcFl / vsize as RtcFl;
 
@@ -40,24 +44,46 @@ impl Camera {
             hsize,
             vsize,
             fov,
-            transform,
+            transform: CachedTransform::default(),
             pixel_size,
             half_width,
             half_height,
+            tone_mapping: ToneMapping::default(),
+            antialiasing: AntiAliasing::default(),
         }
     }
+
+    pub fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
 }
 
 pub fn ray_for_pixel(camera: &Camera, px: usize, py: usize) -> Ray {
-    // the offset from the edge of the canvas to the pixel's center
-    let xoffset = (px as RtcFl + 0.5) * camera.pixel_size;
-    let yoffset = (py as RtcFl + 0.5) * camera.pixel_size;
+    ray_for_subpixel(camera, px as RtcFl + 0.5, py as RtcFl + 0.5)
+}
+
+/// A ray through any point on the canvas, measured in pixels from its top left corner.
+pub fn ray_for_subpixel(camera: &Camera, x: RtcFl, y: RtcFl) -> Ray {
+    // the offset from the edge of the canvas to the point
+    let xoffset = x * camera.pixel_size;
+    let yoffset = y * camera.pixel_size;
 
     let world_x = camera.half_width - xoffset;
     let world_y = camera.half_height - yoffset;
 
-    let pixel = camera.transform.try_inverse().unwrap() * point(world_x, world_y, -1.0);
-    let origin = camera.transform.try_inverse().unwrap() * point(0.0, 0.0, 0.0);
+    let inverse = *camera.transform.inverse();
+    let pixel = inverse * point(world_x, world_y, -1.0);
+    let origin = inverse * point(0.0, 0.0, 0.0);
     let direction = (pixel - origin).normalize();
 
     Ray::new(&origin, &direction)
@@ -65,11 +91,19 @@ pub fn ray_for_pixel(camera: &Camera, px: usize, py: usize) -> Ray {
 
 #[cfg(test)]
 mod tests {
+    use indicatif::ProgressBar;
+
     use crate::{
-        camera::{ray_for_pixel, Camera},
-        matrix::Matrix4,
+        antialias::AntiAliasing,
+        camera::{ray_for_pixel, ray_for_subpixel, Camera},
+        color::Color,
+        matrix::{view_transform, Matrix4, NotInvertible},
+        render::{pixel_color, render},
+        tonemap::{ToneMapOperator, ToneMapping},
+        transformation::{rotation_y, translation},
         tuples::{point, vector},
         util::PI,
+        world::{color_at, create_default_world_for_test},
     };
 
     #[test]
@@ -83,7 +117,7 @@ mod tests {
         assert!(c.hsize == 160);
         assert!(c.vsize == 120);
         assert!(c.fov == PI / 2.0);
-        assert!(c.transform == Matrix4::identity());
+        assert!(*c.transform().matrix() == Matrix4::identity());
     }
 
     #[test]
@@ -107,4 +141,86 @@ mod tests {
         assert_eq!(r.origin, point(0.0, 0.0, 0.0));
         assert_eq!(r.direction, vector(0.0, 0.0, -1.0));
     }
+
+    #[test]
+    fn construct_ray_when_camera_is_transformed() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(rotation_y(PI / 4.0) * translation(0.0, -2.0, 5.0));
+
+        let r = ray_for_pixel(&c, 100, 50);
+
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        assert_eq!(r.origin, point(0.0, 2.0, -5.0));
+        assert_eq!(r.direction, vector(half_sqrt2, 0.0, -half_sqrt2));
+    }
+
+    #[test]
+    fn construct_ray_through_a_point_within_a_pixel() {
+        let c = Camera::new(201, 101, PI / 2.0);
+
+        let center = ray_for_subpixel(&c, 100.5, 50.5);
+        let corner = ray_for_subpixel(&c, 0.0, 0.0);
+
+        assert_eq!(center.direction, ray_for_pixel(&c, 100, 50).direction);
+        assert_eq!(corner.direction, vector(c.half_width, c.half_height, -1.0).normalize());
+    }
+
+    #[test]
+    fn supersampling_averages_rays_across_the_pixel() {
+        let w = create_default_world_for_test();
+        let mut c = Camera::new(1, 1, 0.01);
+        // Looking straight down the edge of the outer sphere, so only some samples hit it
+        c.set_transform(view_transform(
+            point(1.0, 0.0, -5.0),
+            point(1.0, 0.0, 0.0),
+            vector(0.0, 1.0, 0.0),
+        ));
+        c.antialiasing = AntiAliasing {
+            samples: 4,
+            ..Default::default()
+        };
+
+        let expected = [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)]
+            .into_iter()
+            .fold(Color::black(), |sum, (x, y)| {
+                sum + color_at(&w, ray_for_subpixel(&c, x, y)) * 0.25
+            });
+        let color = pixel_color(&c, &w, 0, 0);
+
+        assert_eq!(color, expected);
+        assert_ne!(color, Color::black());
+    }
+
+    #[test]
+    fn rendering_uses_the_cameras_tone_mapping() {
+        let w = create_default_world_for_test();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transform(view_transform(
+            point(0.0, 0.0, -5.0),
+            point(0.0, 0.0, 0.0),
+            vector(0.0, 1.0, 0.0),
+        ));
+        c.tone_mapping = ToneMapping::display(ToneMapOperator::Reinhard);
+
+        let image = render(&c, w, &ProgressBar::hidden());
+
+        assert_eq!(*image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(image.tone_mapping(), &c.tone_mapping);
+        let [r, g, b] = c.tone_mapping.to_u8(*image.pixel_at(5, 5));
+        assert_eq!(&image.rgba_bytes()[(5 * 11 + 5) * 4..][..3], &[r, g, b]);
+    }
+
+    #[test]
+    fn a_camera_rejects_a_view_transform_that_cannot_be_inverted() {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        // Looking straight along the up vector leaves no way to orient the view
+        let singular = view_transform(
+            point(0.0, 0.0, 0.0),
+            point(0.0, 1.0, 0.0),
+            vector(0.0, 1.0, 0.0),
+        );
+
+        assert_eq!(c.try_set_transform(singular), Err(NotInvertible));
+        assert_eq!(*c.transform().matrix(), Matrix4::identity());
+    }
 }
@@ -1,10 +1,100 @@
-use crate::{color::Color, util::RtcFl};
-#[derive(Debug, Clone)]
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    color::Color,
+    tonemap::{srgb_decode, ToneMapping},
+    util::RtcFl,
+};
+
+/// Why an image file could not be read into a `Canvas`.
+#[derive(Debug)]
+pub enum CanvasError {
+    Io(io::Error),
+    Png(png::DecodingError),
+    /// The file extension or magic number is not one we can read
+    UnsupportedFormat(String),
+    InvalidHeader(String),
+    InvalidSample(String),
+    MissingPixelData {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for CanvasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanvasError::Io(e) => write!(f, "Unable to read image: {}", e),
+            CanvasError::Png(e) => write!(f, "Unable to decode PNG: {}", e),
+            CanvasError::UnsupportedFormat(format) => {
+                write!(f, "Unsupported image format: {}", format)
+            }
+            CanvasError::InvalidHeader(reason) => write!(f, "Invalid PPM header: {}", reason),
+            CanvasError::InvalidSample(sample) => write!(f, "Invalid PPM sample: {}", sample),
+            CanvasError::MissingPixelData { expected, found } => write!(
+                f,
+                "Expected {} samples of pixel data but found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CanvasError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CanvasError::Io(e) => Some(e),
+            CanvasError::Png(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CanvasError {
+    fn from(e: io::Error) -> Self {
+        CanvasError::Io(e)
+    }
+}
+
+impl From<png::DecodingError> for CanvasError {
+    fn from(e: png::DecodingError) -> Self {
+        CanvasError::Png(e)
+    }
+}
+
+/// Bits per color sample when writing PNG or PPM files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+/// How `Canvas::write_png` encodes an image, and what it records about the render.
+#[derive(Debug, Clone, Default)]
+pub struct PngOptions {
+    pub depth: BitDepth,
+    /// Add an alpha channel. Canvases are always opaque, so it is only there for
+    /// tools that expect RGBA.
+    pub alpha: bool,
+    pub render_time: Option<Duration>,
+    pub samples: Option<usize>,
+    pub scene: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Canvas {
     pub width: usize,
     pub height: usize,
     data: Vec<Color>,
     rgba: Vec<u8>,
+    tone_mapping: ToneMapping,
 }
 
 impl Canvas {
@@ -22,7 +112,213 @@ impl Canvas {
             height,
             data,
             rgba,
+            tone_mapping: ToneMapping::default(),
+        }
+    }
+
+    pub fn with_tone_mapping(width: usize, height: usize, tone_mapping: ToneMapping) -> Self {
+        Self {
+            tone_mapping,
+            ..Self::new(width, height)
+        }
+    }
+
+    /// How colors are turned into 8 and 16-bit values for `rgba_bytes` and the PNG and
+    /// PPM exporters. HDR and EXR output is always linear and unmapped.
+    pub fn tone_mapping(&self) -> &ToneMapping {
+        &self.tone_mapping
+    }
+
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+        self.tone_mapping = tone_mapping;
+
+        for (index, color) in self.data.iter().enumerate() {
+            let [r, g, b] = tone_mapping.to_u8(*color);
+            self.rgba[index * 4..index * 4 + 3].copy_from_slice(&[r, g, b]);
+        }
+    }
+
+    /// Turns pixels read from an sRGB-encoded image, which most PNG and PPM files are, into the
+    /// linear light that shading works in.
+    pub fn linearize(&mut self) {
+        for color in self.data.iter_mut() {
+            *color = Color::new(
+                srgb_decode(color.red),
+                srgb_decode(color.green),
+                srgb_decode(color.blue),
+            );
+        }
+        self.set_tone_mapping(self.tone_mapping);
+    }
+
+    /// Reads a `.png`, `.ppm` or `.hdr` file, picking the format from the extension. Values are
+    /// returned as stored; see `linearize` for sRGB-encoded files.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CanvasError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Self::from_png(BufReader::new(File::open(path)?)),
+            "ppm" => Self::from_ppm(&fs::read(path)?),
+            "hdr" => crate::hdr::read_hdr_file(path),
+            _ => Err(CanvasError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+
+    /// Parses a plain (P3) or raw (P6) PPM image with any maxval up to 65535.
+    pub fn from_ppm(data: &[u8]) -> Result<Self, CanvasError> {
+        let mut tokens = PpmTokens { data, position: 0 };
+
+        let magic = tokens.next_token().unwrap_or_default();
+        if magic != b"P3" && magic != b"P6" {
+            return Err(CanvasError::UnsupportedFormat(
+                String::from_utf8_lossy(magic).to_string(),
+            ));
+        }
+
+        let mut header_value = |name: &str| -> Result<usize, CanvasError> {
+            let token = tokens
+                .next_token()
+                .ok_or_else(|| CanvasError::InvalidHeader(format!("missing {}", name)))?;
+            std::str::from_utf8(token)
+                .ok()
+                .and_then(|t| t.parse().ok())
+                .ok_or_else(|| {
+                    CanvasError::InvalidHeader(format!(
+                        "{} is not a number: {}",
+                        name,
+                        String::from_utf8_lossy(token)
+                    ))
+                })
+        };
+        let width = header_value("width")?;
+        let height = header_value("height")?;
+        let maxval = header_value("maxval")?;
+        if !(1..=65535).contains(&maxval) {
+            return Err(CanvasError::InvalidHeader(format!(
+                "maxval must be between 1 and 65535, not {}",
+                maxval
+            )));
+        }
+
+        // The header can claim any size, so nothing is allocated from it until the pixel data
+        // is known to be there
+        let expected = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or_else(|| {
+                CanvasError::InvalidHeader(format!("{} by {} is too large", width, height))
+            })?;
+        let samples = if magic == b"P6" {
+            // Exactly one whitespace byte separates the header from the raster
+            let raster = data.get(tokens.position + 1..).unwrap_or_default();
+            let bytes_per_sample = if maxval < 256 { 1 } else { 2 };
+            let found = raster.len() / bytes_per_sample;
+            if found < expected {
+                return Err(CanvasError::MissingPixelData { expected, found });
+            }
+            if bytes_per_sample == 1 {
+                raster[..expected].iter().map(|&b| b as usize).collect()
+            } else {
+                raster[..expected * 2]
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as usize)
+                    .collect()
+            }
+        } else {
+            let mut samples = Vec::new();
+            while let Some(token) = tokens.next_token() {
+                let sample = std::str::from_utf8(token)
+                    .ok()
+                    .and_then(|t| t.parse().ok())
+                    .ok_or_else(|| {
+                        CanvasError::InvalidSample(String::from_utf8_lossy(token).to_string())
+                    })?;
+                samples.push(sample);
+            }
+            samples
+        };
+
+        if samples.len() < expected {
+            return Err(CanvasError::MissingPixelData {
+                expected,
+                found: samples.len(),
+            });
+        }
+        if let Some(sample) = samples[..expected].iter().find(|&&s| s > maxval) {
+            return Err(CanvasError::InvalidSample(format!(
+                "{} is larger than maxval {}",
+                sample, maxval
+            )));
+        }
+
+        let mut canvas = Self::new(width, height);
+        let scale = maxval as RtcFl;
+        for (index, rgb) in samples[..expected].chunks_exact(3).enumerate() {
+            let color = Color::new(
+                rgb[0] as RtcFl / scale,
+                rgb[1] as RtcFl / scale,
+                rgb[2] as RtcFl / scale,
+            );
+            canvas.write_pixel(index % width, index / width, color);
         }
+
+        Ok(canvas)
+    }
+
+    /// Decodes an 8 or 16-bit PNG. Grayscale is spread over all three channels, and any
+    /// alpha channel is ignored.
+    pub fn from_png(reader: impl Read) -> Result<Self, CanvasError> {
+        let mut decoder = png::Decoder::new(reader);
+        // Unpack palettes and low bit depths, so every sample is 8 or 16 bits
+        decoder.set_transformations(png::Transformations::EXPAND);
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        let bytes_per_sample = match info.bit_depth {
+            png::BitDepth::Sixteen => 2,
+            _ => 1,
+        };
+        let max = if bytes_per_sample == 2 {
+            65535.0
+        } else {
+            255.0
+        };
+        let channels = info.color_type.samples();
+
+        let mut canvas = Self::new(info.width as usize, info.height as usize);
+        for (y, line) in buffer
+            .chunks(info.line_size)
+            .take(info.height as usize)
+            .enumerate()
+        {
+            for (x, pixel) in line
+                .chunks_exact(channels * bytes_per_sample)
+                .take(canvas.width)
+                .enumerate()
+            {
+                let sample = |c: usize| {
+                    let value = if bytes_per_sample == 2 {
+                        u16::from_be_bytes([pixel[2 * c], pixel[2 * c + 1]]) as RtcFl
+                    } else {
+                        pixel[c] as RtcFl
+                    };
+                    value / max
+                };
+                let color = match channels {
+                    1 | 2 => Color::new(sample(0), sample(0), sample(0)),
+                    _ => Color::new(sample(0), sample(1), sample(2)),
+                };
+                canvas.write_pixel(x, y, color);
+            }
+        }
+
+        Ok(canvas)
     }
 
     pub fn data_size(&self) -> usize {
@@ -37,9 +333,10 @@ impl Canvas {
         self.data[index] = color;
 
         let i = index * 4;
-        self.rgba[i] = Self::scale(color.red);
-        self.rgba[i + 1] = Self::scale(color.green);
-        self.rgba[i + 2] = Self::scale(color.blue);
+        let [r, g, b] = self.tone_mapping.to_u8(color);
+        self.rgba[i] = r;
+        self.rgba[i + 1] = g;
+        self.rgba[i + 2] = b;
         //self.rgba[i + 3] = 255;
     }
 
@@ -63,17 +360,38 @@ impl Canvas {
         self.rgba.clone()
     }
 
+    /// Streams the canvas as a binary (P6) PPM, one row at a time.
+    ///
+    /// At `BitDepth::Sixteen` the maxval is 65535 and each sample takes two bytes.
+    pub fn write_ppm(&self, mut writer: impl Write, depth: BitDepth) -> io::Result<()> {
+        let maxval = match depth {
+            BitDepth::Eight => 255,
+            BitDepth::Sixteen => 65535,
+        };
+        write!(writer, "P6\n{} {}\n{}\n", self.width, self.height, maxval)?;
+
+        let bytes_per_sample = if depth == BitDepth::Eight { 1 } else { 2 };
+        let mut row = Vec::with_capacity(self.width * 3 * bytes_per_sample);
+        for line in self.data.chunks(self.width.max(1)) {
+            row.clear();
+            for color in line {
+                self.push_samples(*color, depth, &mut row);
+            }
+            writer.write_all(&row)?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn save_ppm(&self, path: impl AsRef<Path>, depth: BitDepth) -> io::Result<()> {
+        self.write_ppm(BufWriter::new(File::create(path)?), depth)
+    }
+
     pub fn to_ppm(&self) -> String {
         let color_vector: Vec<u8> = self
             .data
             .iter()
-            .flat_map(|col| {
-                vec![
-                    Self::scale(col.red),
-                    Self::scale(col.green),
-                    Self::scale(col.blue),
-                ]
-            })
+            .flat_map(|col| self.tone_mapping.to_u8(*col))
             .collect();
 
         let mut ppm = format!(
@@ -110,19 +428,129 @@ impl Canvas {
         ppm
     }
 
+    /// The canvas encoded as an 8-bit RGB PNG file.
     pub fn to_png(&self) -> Vec<u8> {
-        self.rgba.clone()
+        let mut png = Vec::new();
+        self.write_png(&mut png, &PngOptions::default())
+            .expect("Unable to encode PNG.");
+        png
+    }
+
+    pub fn save_png(
+        &self,
+        path: impl AsRef<Path>,
+        options: &PngOptions,
+    ) -> Result<(), png::EncodingError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_png(&mut writer, options)?;
+        writer.flush()?;
+        Ok(())
     }
 
-    fn scale(v: RtcFl) -> u8 {
-        let scaled = (v * 255.0).round();
-        scaled.clamp(0.0, 255.0) as u8
+    pub fn write_png(
+        &self,
+        writer: impl Write,
+        options: &PngOptions,
+    ) -> Result<(), png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(if options.alpha {
+            png::ColorType::Rgba
+        } else {
+            png::ColorType::Rgb
+        });
+        encoder.set_depth(match options.depth {
+            BitDepth::Eight => png::BitDepth::Eight,
+            BitDepth::Sixteen => png::BitDepth::Sixteen,
+        });
+
+        if self.tone_mapping.srgb {
+            encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        }
+
+        encoder.add_text_chunk("Software".to_string(), "rtc-rs".to_string())?;
+        if let Some(scene) = &options.scene {
+            encoder.add_itxt_chunk("Title".to_string(), scene.clone())?;
+        }
+        if let Some(render_time) = options.render_time {
+            encoder.add_text_chunk(
+                "Render time".to_string(),
+                format!("{:.3} s", render_time.as_secs_f64()),
+            )?;
+        }
+        if let Some(samples) = options.samples {
+            encoder.add_text_chunk("Samples".to_string(), samples.to_string())?;
+        }
+
+        let channels = if options.alpha { 4 } else { 3 };
+        let mut data = Vec::with_capacity(self.data.len() * channels * 2);
+        for color in &self.data {
+            self.push_samples(*color, options.depth, &mut data);
+            if options.alpha {
+                match options.depth {
+                    BitDepth::Eight => data.push(u8::MAX),
+                    BitDepth::Sixteen => data.extend(u16::MAX.to_be_bytes()),
+                }
+            }
+        }
+
+        encoder.write_header()?.write_image_data(&data)
+    }
+
+    /// Appends the tone mapped RGB samples of `color`, big-endian at 16 bits.
+    fn push_samples(&self, color: Color, depth: BitDepth, out: &mut Vec<u8>) {
+        match depth {
+            BitDepth::Eight => out.extend(self.tone_mapping.to_u8(color)),
+            BitDepth::Sixteen => {
+                for sample in self.tone_mapping.to_u16(color) {
+                    out.extend(sample.to_be_bytes());
+                }
+            }
+        }
+    }
+}
+
+/// Whitespace separated PPM tokens, skipping `#` comments.
+struct PpmTokens<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> PpmTokens<'a> {
+    fn next_token(&mut self) -> Option<&'a [u8]> {
+        loop {
+            match self.data.get(self.position)? {
+                b'#' => {
+                    while self.data.get(self.position).is_some_and(|&b| b != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                b if b.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+
+        let start = self.position;
+        while self
+            .data
+            .get(self.position)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+
+        Some(&self.data[start..self.position])
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{canvas::Canvas, color::Color};
+    use std::time::Duration;
+
+    use crate::{
+        canvas::{BitDepth, Canvas, CanvasError, PngOptions},
+        color::Color,
+        tonemap::{ToneMapOperator, ToneMapping},
+    };
 
     #[test]
     fn creating_a_canvas() {
@@ -142,7 +570,7 @@ mod tests {
 
         assert_eq!(c.width, w);
         assert_eq!(c.height, h);
-        assert_eq!(c.data_size(), w as usize * h as usize);
+        assert_eq!(c.data_size(), w * h);
     }
 
     #[test]
@@ -169,7 +597,7 @@ mod tests {
         let l3: String = ppm.lines().skip(2).take(1).collect();
 
         assert!(l1 == "P3");
-        assert!(l2 == format!("{} {}", c.width.to_string(), c.height.to_string()));
+        assert!(l2 == format!("{} {}", c.width, c.height));
         assert!(l3 == "255");
     }
 
@@ -235,4 +663,359 @@ mod tests {
 
         assert!(ppm.ends_with('\n'));
     }
+
+    #[test]
+    fn reading_a_file_with_the_wrong_magic_number() {
+        let ppm = b"P32\n1 1\n255\n0 0 0\n";
+
+        assert!(matches!(
+            Canvas::from_ppm(ppm),
+            Err(CanvasError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn reading_a_ppm_returns_a_canvas_of_the_right_size() {
+        let ppm = b"P3\n10 2\n255\n"
+            .iter()
+            .copied()
+            .chain("0 0 0 ".repeat(20).bytes())
+            .collect::<Vec<_>>();
+
+        let canvas = Canvas::from_ppm(&ppm).unwrap();
+
+        assert_eq!(canvas.width, 10);
+        assert_eq!(canvas.height, 2);
+    }
+
+    #[test]
+    fn reading_pixel_data_from_a_ppm_file() {
+        let ppm = b"P3\n4 3\n255\n\
+255 127 0  0 127 255  127 255 0  255 255 255\n\
+0 0 0  255 0 0  0 255 0  0 0 255\n\
+255 255 0  0 255 255  255 0 255  127 127 127\n";
+
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+        let examples = [
+            (0, 0, Color::new(1.0, 0.49804, 0.0)),
+            (1, 0, Color::new(0.0, 0.49804, 1.0)),
+            (2, 0, Color::new(0.49804, 1.0, 0.0)),
+            (3, 0, Color::new(1.0, 1.0, 1.0)),
+            (0, 1, Color::new(0.0, 0.0, 0.0)),
+            (1, 1, Color::new(1.0, 0.0, 0.0)),
+            (2, 1, Color::new(0.0, 1.0, 0.0)),
+            (3, 1, Color::new(0.0, 0.0, 1.0)),
+            (0, 2, Color::new(1.0, 1.0, 0.0)),
+            (1, 2, Color::new(0.0, 1.0, 1.0)),
+            (2, 2, Color::new(1.0, 0.0, 1.0)),
+            (3, 2, Color::new(0.49804, 0.49804, 0.49804)),
+        ];
+
+        for (x, y, color) in examples {
+            assert_eq!(*canvas.pixel_at(x, y), color);
+        }
+    }
+
+    #[test]
+    fn ppm_parsing_ignores_comment_lines() {
+        let ppm = b"P3\n# this is a comment\n2 1\n# this, too\n255\n# another comment\n255 255 255\n# oh, no, comments in the pixel data!\n255 0 255\n";
+
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+
+        assert_eq!(*canvas.pixel_at(0, 0), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(*canvas.pixel_at(1, 0), Color::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn ppm_parsing_allows_an_rgb_triple_to_span_lines() {
+        let ppm = b"P3\n1 1\n255\n51\n153\n\n204\n";
+
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+
+        assert_eq!(*canvas.pixel_at(0, 0), Color::new(0.2, 0.6, 0.8));
+    }
+
+    #[test]
+    fn ppm_parsing_respects_the_scale_setting() {
+        let ppm = b"P3\n2 2\n100\n100 100 100  50 50 50\n75 50 25  0 0 0\n";
+
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+
+        assert_eq!(*canvas.pixel_at(0, 1), Color::new(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn reading_raw_ppm_with_8_and_16_bit_samples() {
+        let p6 = b"P6\n2 1\n255\n\xff\x00\x00\x00\x00\xff";
+        let p6_wide = b"P6 # wide samples\n1 1 65535\n\xff\xff\x80\x00\x00\x00";
+
+        let canvas = Canvas::from_ppm(p6).unwrap();
+        let wide = Canvas::from_ppm(p6_wide).unwrap();
+
+        assert_eq!(*canvas.pixel_at(0, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(*canvas.pixel_at(1, 0), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(*wide.pixel_at(0, 0), Color::new(1.0, 0.50001, 0.0));
+    }
+
+    #[test]
+    fn reading_a_ppm_with_too_little_pixel_data_fails() {
+        let ppm = b"P3\n2 1\n255\n255 0 0\n";
+
+        assert!(matches!(
+            Canvas::from_ppm(ppm),
+            Err(CanvasError::MissingPixelData {
+                expected: 6,
+                found: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn reading_a_ppm_with_bad_samples_fails() {
+        assert!(matches!(
+            Canvas::from_ppm(b"P3\n1 1\n255\n0 red 0\n"),
+            Err(CanvasError::InvalidSample(_))
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(b"P3\n1 1\n15\n0 16 0\n"),
+            Err(CanvasError::InvalidSample(_))
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(b"P3\n1 x\n255\n0 0 0\n"),
+            Err(CanvasError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn reading_a_ppm_with_an_oversized_header_fails_without_allocating() {
+        assert!(matches!(
+            Canvas::from_ppm(b"P3\n100000 100000\n255\n0 0 0\n"),
+            Err(CanvasError::MissingPixelData { found: 3, .. })
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(b"P6\n100000 100000\n255\n\x00\x00\x00"),
+            Err(CanvasError::MissingPixelData { found: 3, .. })
+        ));
+        assert!(matches!(
+            Canvas::from_ppm(b"P3\n18446744073709551615 2\n255\n0 0 0\n"),
+            Err(CanvasError::InvalidHeader(_))
+        ));
+    }
+
+    fn encode_png_for_test(
+        width: u32,
+        height: u32,
+        color: png::ColorType,
+        depth: png::BitDepth,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(color);
+        encoder.set_depth(depth);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(data)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn reading_an_8_bit_rgba_png() {
+        let png = encode_png_for_test(
+            2,
+            1,
+            png::ColorType::Rgba,
+            png::BitDepth::Eight,
+            &[255, 0, 0, 255, 0, 51, 255, 0],
+        );
+
+        let canvas = Canvas::from_png(png.as_slice()).unwrap();
+
+        assert_eq!(*canvas.pixel_at(0, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(*canvas.pixel_at(1, 0), Color::new(0.0, 0.2, 1.0));
+    }
+
+    #[test]
+    fn reading_a_16_bit_rgb_png() {
+        let png = encode_png_for_test(
+            1,
+            1,
+            png::ColorType::Rgb,
+            png::BitDepth::Sixteen,
+            &[0xff, 0xff, 0x80, 0x00, 0x00, 0x00],
+        );
+
+        let canvas = Canvas::from_png(png.as_slice()).unwrap();
+
+        assert_eq!(*canvas.pixel_at(0, 0), Color::new(1.0, 0.50001, 0.0));
+    }
+
+    #[test]
+    fn reading_a_grayscale_png() {
+        let png = encode_png_for_test(
+            2,
+            1,
+            png::ColorType::Grayscale,
+            png::BitDepth::Eight,
+            &[0, 102],
+        );
+
+        let canvas = Canvas::from_png(png.as_slice()).unwrap();
+
+        assert_eq!(*canvas.pixel_at(0, 0), Color::black());
+        assert_eq!(*canvas.pixel_at(1, 0), Color::new(0.4, 0.4, 0.4));
+    }
+
+    #[test]
+    fn reading_something_that_is_not_a_png_fails() {
+        assert!(matches!(
+            Canvas::from_png(&b"P3\n1 1\n255\n0 0 0\n"[..]),
+            Err(CanvasError::Png(_))
+        ));
+    }
+
+    #[test]
+    fn to_png_encodes_an_actual_png() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(2, 1, Color::new(1.0, 0.6, 0.2));
+
+        let png = canvas.to_png();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_eq!(Canvas::from_png(png.as_slice()).unwrap(), canvas);
+    }
+
+    #[test]
+    fn writing_png_with_each_depth_and_alpha() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.6, 0.0));
+        canvas.write_pixel(1, 1, Color::new(0.2, 0.4, 0.6));
+
+        for depth in [BitDepth::Eight, BitDepth::Sixteen] {
+            for alpha in [false, true] {
+                let options = PngOptions {
+                    depth,
+                    alpha,
+                    ..Default::default()
+                };
+                let mut png = Vec::new();
+                canvas.write_png(&mut png, &options).unwrap();
+
+                let info = png::Decoder::new(png.as_slice()).read_info().unwrap();
+                let info = info.info();
+                assert_eq!(
+                    info.bit_depth as u8,
+                    if depth == BitDepth::Eight { 8 } else { 16 }
+                );
+                assert_eq!(info.color_type.samples(), if alpha { 4 } else { 3 });
+
+                let read_back = Canvas::from_png(png.as_slice()).unwrap();
+                assert_eq!(*read_back.pixel_at(0, 0), Color::new(1.0, 0.6, 0.0));
+                assert_eq!(*read_back.pixel_at(1, 1), Color::new(0.2, 0.4, 0.6));
+            }
+        }
+    }
+
+    #[test]
+    fn writing_png_metadata() {
+        let canvas = Canvas::new(1, 1);
+        let options = PngOptions {
+            render_time: Some(Duration::from_millis(1500)),
+            samples: Some(16),
+            scene: Some("Chapter 9".to_string()),
+            ..Default::default()
+        };
+        let mut png = Vec::new();
+
+        canvas.write_png(&mut png, &options).unwrap();
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buffer).unwrap();
+        reader.finish().unwrap();
+        let info = reader.info();
+        let text: Vec<(&str, &str)> = info
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.as_str(), chunk.text.as_str()))
+            .collect();
+
+        assert!(text.contains(&("Software", "rtc-rs")));
+        assert!(text.contains(&("Render time", "1.500 s")));
+        assert!(text.contains(&("Samples", "16")));
+        assert_eq!(info.utf8_text[0].keyword, "Title");
+        assert_eq!(info.utf8_text[0].get_text().unwrap(), "Chapter 9");
+    }
+
+    #[test]
+    fn writing_a_binary_ppm() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.2));
+        canvas.write_pixel(1, 0, Color::new(1.5, -0.5, 0.5));
+        let mut ppm = Vec::new();
+
+        canvas.write_ppm(&mut ppm, BitDepth::Eight).unwrap();
+
+        assert_eq!(ppm, b"P6\n2 1\n255\n\xff\x00\x33\xff\x00\x80");
+    }
+
+    #[test]
+    fn writing_a_16_bit_binary_ppm() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.0, 0.5));
+        let mut ppm = Vec::new();
+
+        canvas.write_ppm(&mut ppm, BitDepth::Sixteen).unwrap();
+
+        assert_eq!(ppm, b"P6\n1 1\n65535\n\xff\xff\x00\x00\x80\x00");
+    }
+
+    #[test]
+    fn binary_ppm_reads_back_into_the_same_canvas() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(0.2, 0.4, 0.6));
+        canvas.write_pixel(2, 1, Color::new(1.0, 0.8, 0.0));
+
+        for depth in [BitDepth::Eight, BitDepth::Sixteen] {
+            let mut ppm = Vec::new();
+            canvas.write_ppm(&mut ppm, depth).unwrap();
+
+            assert_eq!(Canvas::from_ppm(&ppm).unwrap(), canvas);
+        }
+    }
+
+    #[test]
+    fn tone_mapping_applies_to_the_rgba_cache_and_exporters() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, Color::new(0.5, 1.0, 3.0));
+
+        assert_eq!(canvas.rgba_bytes(), &[128, 255, 255, 255]);
+
+        canvas.set_tone_mapping(ToneMapping::display(ToneMapOperator::Reinhard));
+
+        // Reinhard gives 1/3, 1/2 and 3/4, which sRGB encodes as 156, 188 and 225
+        assert_eq!(canvas.rgba_bytes(), &[156, 188, 225, 255]);
+        assert_eq!(canvas.to_ppm(), "P3\n1 1\n255\n156 188 225\n");
+        let mut ppm = Vec::new();
+        canvas.write_ppm(&mut ppm, BitDepth::Eight).unwrap();
+        assert!(ppm.ends_with(&[156, 188, 225]));
+
+        // The stored colors are untouched
+        assert_eq!(*canvas.pixel_at(0, 0), Color::new(0.5, 1.0, 3.0));
+    }
+
+    #[test]
+    fn pixels_written_after_choosing_a_tone_mapping_use_it() {
+        let tm = ToneMapping {
+            exposure: -1.0,
+            ..Default::default()
+        };
+        let mut canvas = Canvas::with_tone_mapping(1, 1, tm);
+
+        canvas.write_pixel(0, 0, Color::new(2.0, 1.0, 0.0));
+
+        assert_eq!(canvas.rgba_bytes(), &[255, 128, 0, 255]);
+    }
 }
@@ -1,9 +1,9 @@
 use crate::{
-    intersections::Intersection,
+    intersections::{Intersection, Intersections},
     ray::Ray,
     shape::Shape,
     tuples::Tuple,
-    util::{RtcFl, SHADOW_EPSILON},
+    util::{equal, RtcFl, SHADOW_EPSILON},
 };
 
 pub struct Computation {
@@ -14,10 +14,20 @@ pub struct Computation {
     pub normalv: Tuple,
     pub inside: bool,
     pub over_point: Tuple,
+    pub under_point: Tuple,
+    pub reflectv: Tuple,
+    /// Refractive index of the material the ray is leaving.
+    pub n1: RtcFl,
+    /// Refractive index of the material the ray is entering.
+    pub n2: RtcFl,
 }
 
 impl Computation {
-    pub fn new(intersection: Intersection, ray: &Ray) -> Self {
+    /// Prepares the shading state for `intersection`, which must be one of `xs`.
+    ///
+    /// The full list is needed to work out which objects the ray is inside of at the hit, and
+    /// so which refractive indices it passes between.
+    pub fn new(intersection: Intersection, ray: &Ray, xs: &Intersections) -> Self {
         let t = intersection.t;
         // TODO: Computation could borrow the shape instead of owning it.
         // This could possibly be more performant, as it does not need
@@ -25,12 +35,15 @@ impl Computation {
         let shape_obj: Box<dyn Shape> = intersection.shape.clone_boxed();
         let point = ray.position(t);
         let eyev = -ray.direction;
-        let mut normalv = intersection.shape.normal_at(point);
+        let mut normalv = intersection.shape.normal_at_hit(point, &intersection);
         let inside = normalv.dot(eyev) < 0.0;
         if inside {
             normalv = -normalv;
         }
         let over_point = point + normalv * SHADOW_EPSILON;
+        let under_point = point - normalv * SHADOW_EPSILON;
+        let reflectv = ray.direction.reflect(normalv);
+        let (n1, n2) = refractive_indices(&intersection, xs);
 
         Self {
             t,
@@ -40,8 +53,70 @@ impl Computation {
             normalv,
             inside,
             over_point,
+            under_point,
+            reflectv,
+            n1,
+            n2,
         }
     }
+
+    /// Schlick's approximation of the Fresnel effect: the fraction of light reflected, as
+    /// opposed to refracted, at this hit.
+    pub fn schlick(&self) -> RtcFl {
+        let mut cos = self.eyev.dot(self.normalv);
+
+        // Total internal reflection can only happen going into a less dense material
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n.powi(2) * (1.0 - cos.powi(2));
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+
+            cos = (1.0 - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+/// Walks the intersections up to the hit, keeping track of which objects the ray is inside,
+/// to find the refractive indices on either side of the hit.
+///
+/// Shapes are told apart by address, since clones share an id.
+fn refractive_indices(hit: &Intersection, xs: &Intersections) -> (RtcFl, RtcFl) {
+    let mut containers: Vec<&dyn Shape> = vec![];
+    let index_of = |containers: &[&dyn Shape]| {
+        containers
+            .last()
+            .map_or(1.0, |s| s.material().refractive_index)
+    };
+
+    let is_hit = |i: &Intersection| equal(i.t, hit.t) && std::ptr::addr_eq(i.shape, hit.shape);
+
+    let mut n1 = 1.0;
+    for i in xs.data.iter() {
+        if is_hit(i) {
+            n1 = index_of(&containers);
+        }
+
+        match containers
+            .iter()
+            .position(|s| std::ptr::addr_eq(*s, i.shape))
+        {
+            Some(p) => {
+                containers.remove(p);
+            }
+            None => containers.push(i.shape),
+        }
+
+        if is_hit(i) {
+            return (n1, index_of(&containers));
+        }
+    }
+
+    (n1, 1.0)
 }
 
 #[cfg(test)]
@@ -49,10 +124,15 @@ mod tests {
 
     use crate::{
         computation::Computation,
-        intersections::Intersection,
+        intersections::{Intersection, Intersections},
+        matrix::Matrix4,
+        plane::Plane,
         ray::Ray,
-        sphere::Sphere,
+        shape::Shape,
+        sphere::{glass_sphere_for_test, Sphere},
+        transformation::{scaling, translation},
         tuples::{point, vector},
+        util::equal,
     };
 
     #[test]
@@ -61,7 +141,7 @@ mod tests {
         let sphere = Sphere::default();
         let i = Intersection::new(4.0, &sphere);
 
-        let comps = Computation::new(i, &ray);
+        let comps = Computation::new(i, &ray, &Intersections::new(vec![i]));
 
         assert_eq!(comps.t, i.t);
         assert_eq!(comps.shape.as_ref().id(), i.shape.id());
@@ -76,9 +156,9 @@ mod tests {
         let shape = Sphere::default();
         let i = Intersection::new(4.0, &shape);
 
-        let comps = Computation::new(i, &ray);
+        let comps = Computation::new(i, &ray, &Intersections::new(vec![i]));
 
-        assert_eq!(comps.inside, false);
+        assert!(!comps.inside);
     }
 
     #[test]
@@ -87,11 +167,127 @@ mod tests {
         let shape = Sphere::default();
         let i = Intersection::new(1.0, &shape);
 
-        let comps = Computation::new(i, &ray);
+        let comps = Computation::new(i, &ray, &Intersections::new(vec![i]));
 
         assert_eq!(comps.point, point(0.0, 0.0, 1.0));
         assert_eq!(comps.eyev, vector(0.0, 0.0, -1.0));
-        assert_eq!(comps.inside, true);
+        assert!(comps.inside);
         assert_eq!(comps.normalv, vector(0.0, 0.0, -1.0));
     }
+
+    #[test]
+    fn precomputing_the_reflection_vector() {
+        let shape = Plane::default();
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        let ray = Ray::new(
+            &point(0.0, 1.0, -1.0),
+            &vector(0.0, -half_sqrt2, half_sqrt2),
+        );
+        let i = Intersection::new((2.0_f32).sqrt(), &shape);
+
+        let comps = Computation::new(i, &ray, &Intersections::new(vec![i]));
+
+        assert_eq!(comps.reflectv, vector(0.0, half_sqrt2, half_sqrt2));
+    }
+
+    #[test]
+    fn finding_n1_and_n2_at_various_intersections() {
+        let mut a = glass_sphere_for_test();
+        a.set_transform(scaling(2.0, 2.0, 2.0));
+        a.material.refractive_index = 1.5;
+        let mut b = glass_sphere_for_test();
+        b.set_transform(translation(0.0, 0.0, -0.25));
+        b.material.refractive_index = 2.0;
+        let mut c = glass_sphere_for_test();
+        c.set_transform(translation(0.0, 0.0, 0.25));
+        c.material.refractive_index = 2.5;
+        let ray = Ray::new(&point(0.0, 0.0, -4.0), &vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ]);
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+
+        for (index, (n1, n2)) in expected.into_iter().enumerate() {
+            let comps = Computation::new(xs.data[index], &ray, &xs);
+
+            assert_eq!(comps.n1, n1);
+            assert_eq!(comps.n2, n2);
+        }
+    }
+
+    #[test]
+    fn cloned_glass_spheres_are_separate_containers() {
+        let mut outer = glass_sphere_for_test();
+        outer.set_transform(scaling(2.0, 2.0, 2.0));
+        let mut inner = outer.clone();
+        inner.set_transform(Matrix4::identity());
+        inner.material.refractive_index = 2.0;
+        let ray = Ray::new(&point(0.0, 0.0, -4.0), &vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(2.0, &outer),
+            Intersection::new(3.0, &inner),
+            Intersection::new(5.0, &inner),
+            Intersection::new(6.0, &outer),
+        ]);
+        let expected = [(1.0, 1.5), (1.5, 2.0), (2.0, 1.5), (1.5, 1.0)];
+
+        assert_eq!(inner.id(), outer.id());
+        for (index, (n1, n2)) in expected.into_iter().enumerate() {
+            let comps = Computation::new(xs.data[index], &ray, &xs);
+
+            assert_eq!((comps.n1, comps.n2), (n1, n2));
+        }
+    }
+
+    #[test]
+    fn schlick_approximation_under_total_internal_reflection() {
+        let shape = glass_sphere_for_test();
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        let ray = Ray::new(&point(0.0, 0.0, half_sqrt2), &vector(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(-half_sqrt2, &shape),
+            Intersection::new(half_sqrt2, &shape),
+        ]);
+
+        let comps = Computation::new(xs.data[1], &ray, &xs);
+
+        assert_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn schlick_approximation_with_a_perpendicular_viewing_angle() {
+        let shape = glass_sphere_for_test();
+        let ray = Ray::new(&point(0.0, 0.0, 0.0), &vector(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(-1.0, &shape),
+            Intersection::new(1.0, &shape),
+        ]);
+
+        let comps = Computation::new(xs.data[1], &ray, &xs);
+
+        assert!(equal(comps.schlick(), 0.04));
+    }
+
+    #[test]
+    fn schlick_approximation_with_small_angle_and_n2_greater_than_n1() {
+        let shape = glass_sphere_for_test();
+        let ray = Ray::new(&point(0.0, 0.99, -2.0), &vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![Intersection::new(1.8589, &shape)]);
+
+        let comps = Computation::new(xs.data[0], &ray, &xs);
+
+        assert!(equal(comps.schlick(), 0.48873));
+    }
 }
@@ -0,0 +1,316 @@
+use crate::{
+    bounds::BoundingBox,
+    cylinder::check_cap,
+    intersections::Intersection,
+    material::Material,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
+    ray::Ray,
+    shape::{next_shape_id, Shape},
+    tuples::{point, vector, Tuple},
+    util::{RtcFl, EPSILON},
+};
+
+/// A double-napped cone around the y axis with its tip at the origin, optionally
+/// truncated between `minimum` and `maximum` and capped when `closed` is set.
+#[derive(Debug, Clone)]
+pub struct Cone {
+    pub id: usize,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
+    pub material: Material,
+    pub minimum: RtcFl,
+    pub maximum: RtcFl,
+    pub closed: bool,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self {
+            id: next_shape_id(),
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
+            material: Material::default(),
+            minimum: -RtcFl::INFINITY,
+            maximum: RtcFl::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl PartialEq for Cone {
+    fn eq(&self, other: &Self) -> bool {
+        self.material == other.material
+            && self.transform == other.transform
+            && self.minimum == other.minimum
+            && self.maximum == other.maximum
+            && self.closed == other.closed
+    }
+}
+
+impl Cone {
+    pub fn new(transform: Matrix4, material: Material) -> Self {
+        Self {
+            transform: CachedTransform::new(transform),
+            material,
+            ..Default::default()
+        }
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
+
+    /// A cone cut off at `minimum` and `maximum` along the y axis.
+    pub fn truncated(minimum: RtcFl, maximum: RtcFl, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+            ..Default::default()
+        }
+    }
+
+    fn intersect_caps<'s>(&'s self, ray: &Ray, xs: &mut Vec<Intersection<'s>>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        // The radius of a cone's cap is the absolute y value where it is cut off
+        let t = (self.minimum - ray.origin.y) / ray.direction.y;
+        if check_cap(ray, t, self.minimum.abs()) {
+            xs.push(Intersection::new(t, self));
+        }
+
+        let t = (self.maximum - ray.origin.y) / ray.direction.y;
+        if check_cap(ray, t, self.maximum.abs()) {
+            xs.push(Intersection::new(t, self));
+        }
+    }
+}
+
+impl Shape for Cone {
+    fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
+        let transformed_ray = ray.transform(*self.transform.inverse());
+
+        let o = transformed_ray.origin;
+        let d = transformed_ray.direction;
+
+        let a = d.x.powi(2) - d.y.powi(2) + d.z.powi(2);
+        let b = 2.0 * o.x * d.x - 2.0 * o.y * d.y + 2.0 * o.z * d.z;
+        let c = o.x.powi(2) - o.y.powi(2) + o.z.powi(2);
+
+        let mut xs = vec![];
+
+        let push_if_within = |t: RtcFl, xs: &mut Vec<Intersection<'s>>| {
+            let y = o.y + t * d.y;
+            if self.minimum < y && y < self.maximum {
+                xs.push(Intersection::new(t, self));
+            }
+        };
+
+        if a.abs() < EPSILON {
+            // The ray is parallel to one of the cone's halves, so it can only hit the other one
+            if b.abs() >= EPSILON {
+                push_if_within(-c / (2.0 * b), &mut xs);
+            }
+        } else {
+            let discriminant = b.powi(2) - 4.0 * a * c;
+
+            if discriminant < -EPSILON {
+                return vec![];
+            }
+
+            // Rays grazing the cone can come out slightly negative in f32, treat them as tangent
+            let discriminant = discriminant.max(0.0);
+
+            let mut t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+            let mut t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            push_if_within(t0, &mut xs);
+            push_if_within(t1, &mut xs);
+        }
+
+        self.intersect_caps(&transformed_ray, &mut xs);
+
+        xs
+    }
+
+    fn normal_at(&self, world_point: Tuple) -> Tuple {
+        let object_point = self.world_to_object(world_point);
+
+        let dist = object_point.x.powi(2) + object_point.z.powi(2);
+
+        let object_normal =
+            if dist < self.maximum.powi(2) && object_point.y >= self.maximum - EPSILON {
+                vector(0.0, 1.0, 0.0)
+            } else if dist < self.minimum.powi(2) && object_point.y <= self.minimum + EPSILON {
+                vector(0.0, -1.0, 0.0)
+            } else {
+                let mut y = dist.sqrt();
+                if object_point.y > 0.0 {
+                    y = -y;
+                }
+                vector(object_point.x, y, object_point.z)
+            };
+
+        self.normal_to_world(object_normal)
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+
+        BoundingBox::new(
+            point(-limit, self.minimum, -limit),
+            point(limit, self.maximum, limit),
+        )
+    }
+
+    fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cone::Cone,
+        ray::Ray,
+        shape::Shape,
+        tuples::{point, vector},
+        util::{equal, RtcFl},
+    };
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray() {
+        let shape = Cone::default();
+        let examples = [
+            (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 5.0, 5.0),
+            (
+                point(0.0, 0.0, -5.0),
+                vector(1.0, 1.0, 1.0),
+                8.66025,
+                8.66025,
+            ),
+            (
+                point(1.0, 1.0, -5.0),
+                vector(-0.5, -1.0, 1.0),
+                4.55006,
+                49.44994,
+            ),
+        ];
+
+        for (origin, direction, t0, t1) in examples {
+            let r = Ray::new(&origin, &direction.normalize());
+            let xs = shape.intersect(&r);
+
+            assert_eq!(xs.len(), 2);
+            // f32 loses a few digits on the far intersection, so compare a little more loosely
+            assert!((xs[0].t - t0).abs() < 0.001);
+            assert!((xs[1].t - t1).abs() < 0.001);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_cone_with_a_ray_parallel_to_one_of_its_halves() {
+        let shape = Cone::default();
+        let r = Ray::new(&point(0.0, 0.0, -1.0), &vector(0.0, 1.0, 1.0).normalize());
+
+        let xs = shape.intersect(&r);
+
+        assert_eq!(xs.len(), 1);
+        assert!(equal(xs[0].t, 0.35355));
+    }
+
+    #[test]
+    fn intersecting_a_cones_end_caps() {
+        let shape = Cone::truncated(-0.5, 0.5, true);
+        let examples = [
+            (point(0.0, 0.0, -5.0), vector(0.0, 1.0, 0.0), 0),
+            (point(0.0, 0.0, -0.25), vector(0.0, 1.0, 1.0), 2),
+            (point(0.0, 0.0, -0.25), vector(0.0, 1.0, 0.0), 4),
+        ];
+
+        for (origin, direction, count) in examples {
+            let r = Ray::new(&origin, &direction.normalize());
+            let xs = shape.intersect(&r);
+
+            assert_eq!(xs.len(), count);
+        }
+    }
+
+    #[test]
+    fn computing_the_normal_vector_on_a_cone() {
+        let shape = Cone::default();
+        let sqrt2: RtcFl = (2.0 as RtcFl).sqrt();
+        let examples = [
+            (point(1.0, 1.0, 1.0), vector(1.0, -sqrt2, 1.0)),
+            (point(-1.0, -1.0, 0.0), vector(-1.0, 1.0, 0.0)),
+        ];
+
+        for (p, expected) in examples {
+            assert_eq!(shape.normal_at(p), expected.normalize());
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_a_cones_end_caps() {
+        let shape = Cone::truncated(-1.0, 1.0, true);
+
+        assert_eq!(shape.normal_at(point(0.5, 1.0, 0.0)), vector(0.0, 1.0, 0.0));
+        assert_eq!(
+            shape.normal_at(point(0.5, -1.0, 0.0)),
+            vector(0.0, -1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn a_bounded_cone_has_a_bounding_box() {
+        let c = Cone::truncated(-5.0, 3.0, false);
+
+        let b = c.bounds();
+
+        assert_eq!(b.min, point(-5.0, -5.0, -5.0));
+        assert_eq!(b.max, point(5.0, 3.0, 5.0));
+    }
+
+    #[test]
+    fn an_unbounded_cone_has_a_bounding_box() {
+        let c = Cone::default();
+
+        let b = c.bounds();
+
+        assert_eq!(b.min.x, -RtcFl::INFINITY);
+        assert_eq!(b.min.y, -RtcFl::INFINITY);
+        assert_eq!(b.max.x, RtcFl::INFINITY);
+        assert_eq!(b.max.y, RtcFl::INFINITY);
+    }
+}
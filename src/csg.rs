@@ -0,0 +1,363 @@
+use crate::{
+    bounds::BoundingBox,
+    bvh::BvhSplit,
+    intersections::{Intersection, Intersections},
+    material::Material,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
+    ray::Ray,
+    shape::{next_shape_id, Shape},
+    tuples::Tuple,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    /// Decides whether a hit on one operand survives the operation.
+    ///
+    /// * `left_hit` - the hit was on the left operand
+    /// * `in_left` - the ray is currently inside the left operand
+    /// * `in_right` - the ray is currently inside the right operand
+    pub fn intersection_allowed(&self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            CsgOperation::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            CsgOperation::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+/// Constructive solid geometry: two shapes combined by a union, intersection or difference.
+///
+/// Either operand can itself be a group or another CSG node.
+#[derive(Debug)]
+pub struct Csg {
+    pub id: usize,
+    pub operation: CsgOperation,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
+    pub material: Material,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>,
+}
+
+impl Clone for Csg {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            operation: self.operation,
+            transform: self.transform,
+            parent_transform: self.parent_transform,
+            material: self.material.clone(),
+            left: self.left.clone_boxed(),
+            right: self.right.clone_boxed(),
+        }
+    }
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Box<dyn Shape>, right: Box<dyn Shape>) -> Self {
+        let mut csg = Self {
+            id: next_shape_id(),
+            operation,
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
+            material: Material::default(),
+            left,
+            right,
+        };
+        csg.update_children();
+        csg
+    }
+
+    pub fn left(&self) -> &dyn Shape {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> &dyn Shape {
+        self.right.as_ref()
+    }
+
+    /// Changes the node's transform and passes it on to both operands.
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+        self.update_children();
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        self.update_children();
+        Ok(())
+    }
+
+    fn update_children(&mut self) {
+        let combined = self.parent_transform.matrix() * self.transform.matrix();
+        self.left.set_parent_transform(combined);
+        self.right.set_parent_transform(combined);
+    }
+
+    /// Walks the sorted intersections, tracking whether the ray is inside each operand,
+    /// and keeps only the ones that lie on the surface of the combined shape.
+    pub fn filter_intersections<'s>(&self, xs: Intersections<'s>) -> Intersections<'s> {
+        let mut in_left = false;
+        let mut in_right = false;
+
+        let mut result = Vec::new();
+
+        for i in xs.data {
+            let left_hit = self.left.includes(i.shape);
+
+            if self
+                .operation
+                .intersection_allowed(left_hit, in_left, in_right)
+            {
+                result.push(i);
+            }
+
+            if left_hit {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+        }
+
+        Intersections::new(result)
+    }
+}
+
+impl Shape for Csg {
+    fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
+        let local_ray = ray.transform(*self.transform.inverse());
+
+        let mut xs = self.left.intersect(&local_ray);
+        xs.extend(self.right.intersect(&local_ray));
+
+        self.filter_intersections(Intersections::new(xs)).data
+    }
+
+    fn normal_at(&self, _world_point: Tuple) -> Tuple {
+        // Intersections always point at the operand that was hit, never at the CSG node
+        panic!("A CSG node has no surface of its own to compute a normal for.");
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = self.left.parent_space_bounds();
+        b.merge(&self.right.parent_space_bounds());
+        b
+    }
+
+    fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+        self.update_children();
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn includes(&self, shape: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, shape) || self.left.includes(shape) || self.right.includes(shape)
+    }
+
+    fn build_bvh(&mut self, split: BvhSplit) {
+        self.left.build_bvh(split);
+        self.right.build_bvh(split);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        csg::{Csg, CsgOperation},
+        cube::Cube,
+        intersections::{Intersection, Intersections},
+        material::Material,
+        ray::Ray,
+        shape::Shape,
+        sphere::Sphere,
+        transformation::translation,
+        tuples::{point, vector},
+        world::World,
+    };
+
+    #[test]
+    fn csg_is_created_with_an_operation_and_two_shapes() {
+        let s1 = Sphere::default();
+        let s2 = Cube::default();
+        let (id1, id2) = (s1.id, s2.id);
+
+        let c = Csg::new(CsgOperation::Union, Box::new(s1), Box::new(s2));
+
+        assert_eq!(c.operation, CsgOperation::Union);
+        assert_eq!(c.left().id(), id1);
+        assert_eq!(c.right().id(), id2);
+    }
+
+    #[test]
+    fn evaluating_the_rule_for_a_csg_operation() {
+        use CsgOperation::*;
+
+        #[rustfmt::skip]
+        let examples = [
+            (Union, true, true, true, false),
+            (Union, true, true, false, true),
+            (Union, true, false, true, false),
+            (Union, true, false, false, true),
+            (Union, false, true, true, false),
+            (Union, false, true, false, false),
+            (Union, false, false, true, true),
+            (Union, false, false, false, true),
+            (Intersection, true, true, true, true),
+            (Intersection, true, true, false, false),
+            (Intersection, true, false, true, true),
+            (Intersection, true, false, false, false),
+            (Intersection, false, true, true, true),
+            (Intersection, false, true, false, true),
+            (Intersection, false, false, true, false),
+            (Intersection, false, false, false, false),
+            (Difference, true, true, true, false),
+            (Difference, true, true, false, true),
+            (Difference, true, false, true, false),
+            (Difference, true, false, false, true),
+            (Difference, false, true, true, true),
+            (Difference, false, true, false, true),
+            (Difference, false, false, true, false),
+            (Difference, false, false, false, false),
+        ];
+
+        for (op, lhit, inl, inr, result) in examples {
+            assert_eq!(op.intersection_allowed(lhit, inl, inr), result);
+        }
+    }
+
+    #[test]
+    fn filtering_a_list_of_intersections() {
+        let examples = [
+            (CsgOperation::Union, 0, 3),
+            (CsgOperation::Intersection, 1, 2),
+            (CsgOperation::Difference, 0, 1),
+        ];
+
+        for (op, x0, x1) in examples {
+            let s1 = Sphere::default();
+            let s2 = Cube::default();
+            let c = Csg::new(op, Box::new(s1), Box::new(s2));
+
+            let xs_data = vec![
+                Intersection::new(1.0, c.left()),
+                Intersection::new(2.0, c.right()),
+                Intersection::new(3.0, c.left()),
+                Intersection::new(4.0, c.right()),
+            ];
+            let xs = Intersections::new(xs_data.clone());
+
+            let result = c.filter_intersections(xs);
+
+            assert_eq!(result.data.len(), 2);
+            assert_eq!(result.data[0], xs_data[x0]);
+            assert_eq!(result.data[1], xs_data[x1]);
+        }
+    }
+
+    #[test]
+    fn ray_misses_a_csg_object() {
+        let c = Csg::new(
+            CsgOperation::Union,
+            Box::new(Sphere::default()),
+            Box::new(Cube::default()),
+        );
+        let r = Ray::new(&point(0.0, 2.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        assert!(c.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_hits_a_csg_object() {
+        let s1 = Sphere::default();
+        let s2 = Sphere::new(translation(0.0, 0.0, 0.5), Material::default());
+        let (id1, id2) = (s1.id, s2.id);
+        let c = Csg::new(CsgOperation::Union, Box::new(s1), Box::new(s2));
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        let xs = c.intersect(&r);
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert_eq!(xs[0].shape.id(), id1);
+        assert_eq!(xs[1].t, 6.5);
+        assert_eq!(xs[1].shape.id(), id2);
+    }
+
+    #[test]
+    fn nested_csg_objects_keep_track_of_their_operands() {
+        // A sphere with a cube-shaped bite taken out of it, unioned with a second sphere
+        let bite = Csg::new(
+            CsgOperation::Difference,
+            Box::new(Sphere::default()),
+            Box::new(Cube::new(translation(0.0, 0.0, -1.5), Material::default())),
+        );
+        let other = Sphere::new(translation(5.0, 0.0, 0.0), Material::default());
+        let c = Csg::new(CsgOperation::Union, Box::new(bite), Box::new(other));
+
+        let mut w = World::default();
+        w.add_object(c);
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+
+        // The front of the sphere is bitten away, so the ray enters through the cube's back face
+        assert_eq!(xs.data.len(), 2);
+        assert_eq!(xs.hit().unwrap().t, 4.5);
+        assert_eq!(xs.data[1].t, 6.0);
+    }
+
+    #[test]
+    fn a_csg_of_a_shape_and_its_clone_tells_the_operands_apart() {
+        let s = Sphere::default();
+        let mut moved = s.clone();
+        moved.set_transform(translation(0.0, 0.0, 0.5));
+        let c = Csg::new(CsgOperation::Difference, Box::new(s), Box::new(moved));
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        let xs = c.intersect(&r);
+
+        // In through the sphere's front, out through the clone's front
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 4.0);
+        assert_eq!(xs[1].t, 4.5);
+        assert!(c.left().includes(xs[0].shape));
+        assert!(c.right().includes(xs[1].shape));
+    }
+
+    #[test]
+    fn a_csg_shape_has_a_bounding_box_that_contains_its_children() {
+        let left = Sphere::default();
+        let right = Sphere::new(translation(2.0, 3.0, 4.0), Material::default());
+        let c = Csg::new(CsgOperation::Difference, Box::new(left), Box::new(right));
+
+        let b = c.bounds();
+
+        assert_eq!(b.min, point(-1.0, -1.0, -1.0));
+        assert_eq!(b.max, point(3.0, 4.0, 5.0));
+    }
+}
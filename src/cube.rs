@@ -0,0 +1,271 @@
+use crate::{
+    bounds::{check_axis, BoundingBox},
+    intersections::Intersection,
+    material::Material,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
+    ray::Ray,
+    shape::{next_shape_id, Shape},
+    tuples::{point, vector, Tuple},
+};
+
+/// An axis-aligned cube spanning -1..1 on every axis in object space.
+#[derive(Debug, Clone)]
+pub struct Cube {
+    pub id: usize,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
+    pub material: Material,
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        Self {
+            id: next_shape_id(),
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
+            material: Material::default(),
+        }
+    }
+}
+
+impl PartialEq for Cube {
+    fn eq(&self, other: &Self) -> bool {
+        self.material == other.material && self.transform == other.transform
+    }
+}
+
+impl Cube {
+    pub fn new(transform: Matrix4, material: Material) -> Self {
+        Self {
+            id: next_shape_id(),
+            transform: CachedTransform::new(transform),
+            parent_transform: CachedTransform::default(),
+            material,
+        }
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
+}
+
+impl Shape for Cube {
+    fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
+        let transformed_ray = ray.transform(*self.transform.inverse());
+
+        let (o, d) = (transformed_ray.origin, transformed_ray.direction);
+        let (xtmin, xtmax) = check_axis(o.x, d.x, -1.0, 1.0);
+        let (ytmin, ytmax) = check_axis(o.y, d.y, -1.0, 1.0);
+        let (ztmin, ztmax) = check_axis(o.z, d.z, -1.0, 1.0);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            vec![]
+        } else {
+            vec![Intersection::new(tmin, self), Intersection::new(tmax, self)]
+        }
+    }
+
+    fn normal_at(&self, world_point: Tuple) -> Tuple {
+        let object_point = self.world_to_object(world_point);
+
+        // The face we are on is given by the component with the largest magnitude
+        let maxc = object_point
+            .x
+            .abs()
+            .max(object_point.y.abs())
+            .max(object_point.z.abs());
+
+        let object_normal = if maxc == object_point.x.abs() {
+            vector(object_point.x, 0.0, 0.0)
+        } else if maxc == object_point.y.abs() {
+            vector(0.0, object_point.y, 0.0)
+        } else {
+            vector(0.0, 0.0, object_point.z)
+        };
+
+        self.normal_to_world(object_normal)
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
+
+    fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cube::Cube,
+        material::Material,
+        matrix::Matrix4,
+        ray::Ray,
+        shape::Shape,
+        transformation::{scaling, translation},
+        tuples::{point, vector},
+    };
+
+    #[test]
+    fn creating_a_cube() {
+        let c1 = Cube::default();
+        let c2 = Cube::default();
+
+        assert_ne!(c1.id, c2.id);
+        assert_eq!(*c1.transform.matrix(), Matrix4::identity());
+        assert_eq!(c1.material, Material::default());
+    }
+
+    #[test]
+    fn ray_intersects_a_cube() {
+        let c = Cube::default();
+        let examples = [
+            // +x
+            (point(5.0, 0.5, 0.0), vector(-1.0, 0.0, 0.0), 4.0, 6.0),
+            // -x
+            (point(-5.0, 0.5, 0.0), vector(1.0, 0.0, 0.0), 4.0, 6.0),
+            // +y
+            (point(0.5, 5.0, 0.0), vector(0.0, -1.0, 0.0), 4.0, 6.0),
+            // -y
+            (point(0.5, -5.0, 0.0), vector(0.0, 1.0, 0.0), 4.0, 6.0),
+            // +z
+            (point(0.5, 0.0, 5.0), vector(0.0, 0.0, -1.0), 4.0, 6.0),
+            // -z
+            (point(0.5, 0.0, -5.0), vector(0.0, 0.0, 1.0), 4.0, 6.0),
+            // inside
+            (point(0.0, 0.5, 0.0), vector(0.0, 0.0, 1.0), -1.0, 1.0),
+        ];
+
+        for (origin, direction, t1, t2) in examples {
+            let r = Ray::new(&origin, &direction);
+            let xs = c.intersect(&r);
+
+            assert_eq!(xs.len(), 2);
+            assert_eq!(xs[0].t, t1);
+            assert_eq!(xs[1].t, t2);
+        }
+    }
+
+    #[test]
+    fn ray_misses_a_cube() {
+        let c = Cube::default();
+        let examples = [
+            (point(-2.0, 0.0, 0.0), vector(0.2673, 0.5345, 0.8018)),
+            (point(0.0, -2.0, 0.0), vector(0.8018, 0.2673, 0.5345)),
+            (point(0.0, 0.0, -2.0), vector(0.5345, 0.8018, 0.2673)),
+            (point(2.0, 0.0, 2.0), vector(0.0, 0.0, -1.0)),
+            (point(0.0, 2.0, 2.0), vector(0.0, -1.0, 0.0)),
+            (point(2.0, 2.0, 0.0), vector(-1.0, 0.0, 0.0)),
+        ];
+
+        for (origin, direction) in examples {
+            let r = Ray::new(&origin, &direction);
+            let xs = c.intersect(&r);
+
+            assert!(xs.is_empty());
+        }
+    }
+
+    #[test]
+    fn intersect_scaled_cube_with_ray() {
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let c = Cube::new(scaling(2.0, 2.0, 2.0), Material::default());
+
+        let xs = c.intersect(&r);
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 3.0);
+        assert_eq!(xs[1].t, 7.0);
+    }
+
+    #[test]
+    fn intersect_translated_cube_with_ray() {
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let c = Cube::new(translation(5.0, 0.0, 0.0), Material::default());
+
+        let xs = c.intersect(&r);
+
+        assert_eq!(xs.len(), 0);
+    }
+
+    #[test]
+    fn normal_on_the_surface_of_a_cube() {
+        let c = Cube::default();
+        let examples = [
+            (point(1.0, 0.5, -0.8), vector(1.0, 0.0, 0.0)),
+            (point(-1.0, -0.2, 0.9), vector(-1.0, 0.0, 0.0)),
+            (point(-0.4, 1.0, -0.1), vector(0.0, 1.0, 0.0)),
+            (point(0.3, -1.0, -0.7), vector(0.0, -1.0, 0.0)),
+            (point(-0.6, 0.3, 1.0), vector(0.0, 0.0, 1.0)),
+            (point(0.4, 0.4, -1.0), vector(0.0, 0.0, -1.0)),
+            (point(1.0, 1.0, 1.0), vector(1.0, 0.0, 0.0)),
+            (point(-1.0, -1.0, -1.0), vector(-1.0, 0.0, 0.0)),
+        ];
+
+        for (p, expected) in examples {
+            assert_eq!(c.normal_at(p), expected);
+        }
+    }
+
+    #[test]
+    fn computing_normal_on_translated_cube() {
+        let c = Cube::new(translation(0.0, 1.0, 0.0), Material::default());
+
+        let n = c.normal_at(point(0.5, 2.0, 0.2));
+
+        assert_eq!(n, vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn cube_may_be_assigned_a_material() {
+        let c = Cube {
+            material: Material {
+                shininess: 900.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(c.material.shininess, 900.0);
+    }
+
+    #[test]
+    fn a_cube_has_a_bounding_box() {
+        let c = Cube::default();
+
+        let b = c.bounds();
+
+        assert_eq!(b.min, point(-1.0, -1.0, -1.0));
+        assert_eq!(b.max, point(1.0, 1.0, 1.0));
+    }
+}
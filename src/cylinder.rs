@@ -0,0 +1,357 @@
+use crate::{
+    bounds::BoundingBox,
+    intersections::Intersection,
+    material::Material,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
+    ray::Ray,
+    shape::{next_shape_id, Shape},
+    tuples::{point, vector, Tuple},
+    util::{RtcFl, EPSILON},
+};
+
+/// A cylinder of radius 1 around the y axis, optionally truncated between
+/// `minimum` and `maximum` and capped when `closed` is set.
+#[derive(Debug, Clone)]
+pub struct Cylinder {
+    pub id: usize,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
+    pub material: Material,
+    pub minimum: RtcFl,
+    pub maximum: RtcFl,
+    pub closed: bool,
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self {
+            id: next_shape_id(),
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
+            material: Material::default(),
+            minimum: -RtcFl::INFINITY,
+            maximum: RtcFl::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl PartialEq for Cylinder {
+    fn eq(&self, other: &Self) -> bool {
+        self.material == other.material
+            && self.transform == other.transform
+            && self.minimum == other.minimum
+            && self.maximum == other.maximum
+            && self.closed == other.closed
+    }
+}
+
+impl Cylinder {
+    pub fn new(transform: Matrix4, material: Material) -> Self {
+        Self {
+            transform: CachedTransform::new(transform),
+            material,
+            ..Default::default()
+        }
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
+
+    /// A cylinder cut off at `minimum` and `maximum` along the y axis.
+    pub fn truncated(minimum: RtcFl, maximum: RtcFl, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+            ..Default::default()
+        }
+    }
+
+    fn intersect_caps<'s>(&'s self, ray: &Ray, xs: &mut Vec<Intersection<'s>>) {
+        // Caps only matter if the cylinder is closed and the ray could possibly hit them
+        if !self.closed || ray.direction.y.abs() < EPSILON {
+            return;
+        }
+
+        let t = (self.minimum - ray.origin.y) / ray.direction.y;
+        if check_cap(ray, t, 1.0) {
+            xs.push(Intersection::new(t, self));
+        }
+
+        let t = (self.maximum - ray.origin.y) / ray.direction.y;
+        if check_cap(ray, t, 1.0) {
+            xs.push(Intersection::new(t, self));
+        }
+    }
+}
+
+/// Is the point at `t` within `radius` of the y axis?
+pub(crate) fn check_cap(ray: &Ray, t: RtcFl, radius: RtcFl) -> bool {
+    let x = ray.origin.x + t * ray.direction.x;
+    let z = ray.origin.z + t * ray.direction.z;
+
+    x.powi(2) + z.powi(2) <= radius.powi(2) + EPSILON
+}
+
+impl Shape for Cylinder {
+    fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
+        let transformed_ray = ray.transform(*self.transform.inverse());
+
+        let mut xs = vec![];
+
+        let a = transformed_ray.direction.x.powi(2) + transformed_ray.direction.z.powi(2);
+
+        // A ray parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2.0 * transformed_ray.origin.x * transformed_ray.direction.x
+                + 2.0 * transformed_ray.origin.z * transformed_ray.direction.z;
+            let c = transformed_ray.origin.x.powi(2) + transformed_ray.origin.z.powi(2) - 1.0;
+
+            let discriminant = b.powi(2) - 4.0 * a * c;
+
+            if discriminant < 0.0 {
+                return vec![];
+            }
+
+            let mut t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+            let mut t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            for t in [t0, t1] {
+                let y = transformed_ray.origin.y + t * transformed_ray.direction.y;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(&transformed_ray, &mut xs);
+
+        xs
+    }
+
+    fn normal_at(&self, world_point: Tuple) -> Tuple {
+        let object_point = self.world_to_object(world_point);
+
+        let dist = object_point.x.powi(2) + object_point.z.powi(2);
+
+        let object_normal = if dist < 1.0 && object_point.y >= self.maximum - EPSILON {
+            vector(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && object_point.y <= self.minimum + EPSILON {
+            vector(0.0, -1.0, 0.0)
+        } else {
+            vector(object_point.x, 0.0, object_point.z)
+        };
+
+        self.normal_to_world(object_normal)
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            point(-1.0, self.minimum, -1.0),
+            point(1.0, self.maximum, 1.0),
+        )
+    }
+
+    fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cylinder::Cylinder,
+        material::Material,
+        ray::Ray,
+        shape::Shape,
+        transformation::scaling,
+        tuples::{point, vector},
+        util::{equal, RtcFl},
+    };
+
+    #[test]
+    fn ray_misses_a_cylinder() {
+        let cyl = Cylinder::default();
+        let examples = [
+            (point(1.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)),
+            (point(0.0, 0.0, 0.0), vector(0.0, 1.0, 0.0)),
+            (point(0.0, 0.0, -5.0), vector(1.0, 1.0, 1.0)),
+        ];
+
+        for (origin, direction) in examples {
+            let r = Ray::new(&origin, &direction.normalize());
+            let xs = cyl.intersect(&r);
+
+            assert!(xs.is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_cylinder() {
+        let cyl = Cylinder::default();
+        let examples = [
+            (point(1.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 5.0, 5.0),
+            (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 4.0, 6.0),
+            (
+                point(0.5, 0.0, -5.0),
+                vector(0.1, 1.0, 1.0),
+                6.80798,
+                7.08872,
+            ),
+        ];
+
+        for (origin, direction, t0, t1) in examples {
+            let r = Ray::new(&origin, &direction.normalize());
+            let xs = cyl.intersect(&r);
+
+            assert_eq!(xs.len(), 2);
+            assert!(equal(xs[0].t, t0));
+            assert!(equal(xs[1].t, t1));
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_a_cylinder() {
+        let cyl = Cylinder::default();
+        let examples = [
+            (point(1.0, 0.0, 0.0), vector(1.0, 0.0, 0.0)),
+            (point(0.0, 5.0, -1.0), vector(0.0, 0.0, -1.0)),
+            (point(0.0, -2.0, 1.0), vector(0.0, 0.0, 1.0)),
+            (point(-1.0, 1.0, 0.0), vector(-1.0, 0.0, 0.0)),
+        ];
+
+        for (p, expected) in examples {
+            assert_eq!(cyl.normal_at(p), expected);
+        }
+    }
+
+    #[test]
+    fn default_minimum_and_maximum_for_a_cylinder() {
+        let cyl = Cylinder::default();
+
+        assert_eq!(cyl.minimum, -RtcFl::INFINITY);
+        assert_eq!(cyl.maximum, RtcFl::INFINITY);
+        assert!(!cyl.closed);
+    }
+
+    #[test]
+    fn intersecting_a_constrained_cylinder() {
+        let cyl = Cylinder::truncated(1.0, 2.0, false);
+        let examples = [
+            (point(0.0, 1.5, 0.0), vector(0.1, 1.0, 0.0), 0),
+            (point(0.0, 3.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+            (point(0.0, 0.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+            (point(0.0, 2.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+            (point(0.0, 1.0, -5.0), vector(0.0, 0.0, 1.0), 0),
+            (point(0.0, 1.5, -2.0), vector(0.0, 0.0, 1.0), 2),
+        ];
+
+        for (origin, direction, count) in examples {
+            let r = Ray::new(&origin, &direction.normalize());
+            let xs = cyl.intersect(&r);
+
+            assert_eq!(xs.len(), count);
+        }
+    }
+
+    #[test]
+    fn intersecting_the_caps_of_a_closed_cylinder() {
+        let cyl = Cylinder::truncated(1.0, 2.0, true);
+        let examples = [
+            (point(0.0, 3.0, 0.0), vector(0.0, -1.0, 0.0), 2),
+            (point(0.0, 3.0, -2.0), vector(0.0, -1.0, 2.0), 2),
+            (point(0.0, 4.0, -2.0), vector(0.0, -1.0, 1.0), 2),
+            (point(0.0, 0.0, -2.0), vector(0.0, 1.0, 2.0), 2),
+            (point(0.0, -1.0, -2.0), vector(0.0, 1.0, 1.0), 2),
+        ];
+
+        for (origin, direction, count) in examples {
+            let r = Ray::new(&origin, &direction.normalize());
+            let xs = cyl.intersect(&r);
+
+            assert_eq!(xs.len(), count);
+        }
+    }
+
+    #[test]
+    fn normal_vector_on_a_cylinders_end_caps() {
+        let cyl = Cylinder::truncated(1.0, 2.0, true);
+        let examples = [
+            (point(0.0, 1.0, 0.0), vector(0.0, -1.0, 0.0)),
+            (point(0.5, 1.0, 0.0), vector(0.0, -1.0, 0.0)),
+            (point(0.0, 1.0, 0.5), vector(0.0, -1.0, 0.0)),
+            (point(0.0, 2.0, 0.0), vector(0.0, 1.0, 0.0)),
+            (point(0.5, 2.0, 0.0), vector(0.0, 1.0, 0.0)),
+            (point(0.0, 2.0, 0.5), vector(0.0, 1.0, 0.0)),
+        ];
+
+        for (p, expected) in examples {
+            assert_eq!(cyl.normal_at(p), expected);
+        }
+    }
+
+    #[test]
+    fn intersect_scaled_cylinder_with_ray() {
+        let cyl = Cylinder::new(scaling(2.0, 1.0, 2.0), Material::default());
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        let xs = cyl.intersect(&r);
+
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 3.0);
+        assert_eq!(xs[1].t, 7.0);
+    }
+
+    #[test]
+    fn a_bounded_cylinder_has_a_bounding_box() {
+        let cyl = Cylinder::truncated(-5.0, 3.0, false);
+
+        let b = cyl.bounds();
+
+        assert_eq!(b.min, point(-1.0, -5.0, -1.0));
+        assert_eq!(b.max, point(1.0, 3.0, 1.0));
+    }
+
+    #[test]
+    fn an_unbounded_cylinder_has_a_bounding_box() {
+        let cyl = Cylinder::default();
+
+        let b = cyl.bounds();
+
+        assert_eq!(b.min.y, -RtcFl::INFINITY);
+        assert_eq!(b.max.y, RtcFl::INFINITY);
+        assert_eq!(b.min.x, -1.0);
+        assert_eq!(b.max.z, 1.0);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::{
+    bounds::BoundingBox,
+    bvh::{Bvh, BvhSplit},
+    intersections::Intersection,
+    material::Material,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
+    ray::Ray,
+    shape::{next_shape_id, Shape},
+    tuples::Tuple,
+};
+
+/// A collection of shapes that are transformed together.
+///
+/// The group's transform is applied on top of each child's own transform. Children are told
+/// about every group above them through `set_parent_transform`, so they can convert points
+/// and normals between world and object space without walking back up the tree.
+#[derive(Debug)]
+pub struct Group {
+    pub id: usize,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
+    pub material: Material,
+    children: Vec<Box<dyn Shape>>,
+    bvh: Option<Bvh>,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            id: next_shape_id(),
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
+            material: Material::default(),
+            children: vec![],
+            bvh: None,
+        }
+    }
+}
+
+impl Clone for Group {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            transform: self.transform,
+            parent_transform: self.parent_transform,
+            material: self.material.clone(),
+            children: self.children.iter().map(|c| c.clone_boxed()).collect(),
+            bvh: self.bvh.clone(),
+        }
+    }
+}
+
+impl Group {
+    pub fn new(transform: Matrix4) -> Self {
+        Self {
+            transform: CachedTransform::new(transform),
+            ..Default::default()
+        }
+    }
+
+    pub fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub fn add_child(&mut self, child: impl Shape + 'static) {
+        self.add_boxed_child(Box::new(child));
+    }
+
+    pub fn add_boxed_child(&mut self, mut child: Box<dyn Shape>) {
+        child.set_parent_transform(self.parent_transform.matrix() * self.transform.matrix());
+        self.children.push(child);
+        self.bvh = None;
+    }
+
+    pub fn add_boxed_children(&mut self, children: Vec<Box<dyn Shape>>) {
+        for c in children {
+            self.add_boxed_child(c);
+        }
+    }
+
+    /// Changes the group's transform and passes it on to every child.
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+        self.update_children();
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        self.update_children();
+        Ok(())
+    }
+
+    fn update_children(&mut self) {
+        let combined = self.parent_transform.matrix() * self.transform.matrix();
+        for c in self.children.iter_mut() {
+            c.set_parent_transform(combined);
+        }
+    }
+}
+
+impl Shape for Group {
+    fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
+        let local_ray = ray.transform(*self.transform.inverse());
+
+        match &self.bvh {
+            Some(bvh) => bvh.intersect(&self.children, &local_ray),
+            None => self
+                .children
+                .iter()
+                .flat_map(|c| c.intersect(&local_ray))
+                .collect(),
+        }
+    }
+
+    fn normal_at(&self, _world_point: Tuple) -> Tuple {
+        // Intersections always point at the child that was hit, never at the group
+        panic!("A group has no surface of its own to compute a normal for.");
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        for c in self.children.iter() {
+            b.merge(&c.parent_space_bounds());
+        }
+        b
+    }
+
+    fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+        self.update_children();
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+
+    fn includes(&self, shape: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, shape) || self.children.iter().any(|c| c.includes(shape))
+    }
+
+    /// Builds a BVH over the children, after first building one inside each of them.
+    ///
+    /// Adding another child drops the BVH again until this is called once more.
+    fn build_bvh(&mut self, split: BvhSplit) {
+        for c in self.children.iter_mut() {
+            c.build_bvh(split);
+        }
+        self.bvh = Some(Bvh::build(&self.children, split));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use crate::{
+        bvh::BvhSplit,
+        cylinder::Cylinder,
+        group::Group,
+        material::Material,
+        matrix::{Matrix4, NotInvertible},
+        ray::Ray,
+        shape::Shape,
+        sphere::Sphere,
+        transformation::{rotation_y, scaling, translation},
+        tuples::{point, vector},
+        util::PI,
+        world::World,
+    };
+
+    #[test]
+    fn creating_a_new_group() {
+        let g = Group::default();
+
+        assert_eq!(*g.transform().matrix(), Matrix4::identity());
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn adding_a_child_to_a_group() {
+        let mut g = Group::new(translation(1.0, 2.0, 3.0));
+        let s = Sphere::default();
+        let id = s.id;
+
+        g.add_child(s);
+
+        assert_eq!(g.children().len(), 1);
+        assert_eq!(g.children()[0].id(), id);
+        assert_eq!(
+            *g.children()[0].parent_transform().matrix(),
+            translation(1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn a_group_includes_its_nested_children() {
+        let s = Sphere::default();
+        let mut inner = Group::default();
+        inner.add_child(s.clone());
+        let mut outer = Group::default();
+        outer.add_child(inner);
+        let nested = outer.children()[0].as_ref();
+
+        assert!(outer.includes(nested));
+        assert!(outer.includes(&outer));
+        // A clone shares the id but isn't the one in the group
+        assert!(!outer.includes(&s));
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_an_empty_group() {
+        let g = Group::default();
+        let r = Ray::new(&point(0.0, 0.0, 0.0), &vector(0.0, 0.0, 1.0));
+
+        assert!(g.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_nonempty_group() {
+        let mut g = Group::default();
+        let s1 = Sphere::default();
+        let s2 = Sphere::new(translation(0.0, 0.0, -3.0), Material::default());
+        let s3 = Sphere::new(translation(5.0, 0.0, 0.0), Material::default());
+        let (id1, id2) = (s1.id, s2.id);
+        g.add_child(s1);
+        g.add_child(s2);
+        g.add_child(s3);
+
+        let mut w = World::default();
+        w.add_object(g);
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+
+        assert_eq!(xs.data.len(), 4);
+        assert_eq!(xs.data[0].shape.id(), id2);
+        assert_eq!(xs.data[1].shape.id(), id2);
+        assert_eq!(xs.data[2].shape.id(), id1);
+        assert_eq!(xs.data[3].shape.id(), id1);
+    }
+
+    #[test]
+    fn intersecting_a_transformed_group() {
+        let mut g = Group::new(scaling(2.0, 2.0, 2.0));
+        g.add_child(Sphere::new(translation(5.0, 0.0, 0.0), Material::default()));
+        let r = Ray::new(&point(10.0, 0.0, -10.0), &vector(0.0, 0.0, 1.0));
+
+        let xs = g.intersect(&r);
+
+        assert_eq!(xs.len(), 2);
+    }
+
+    fn nested_sphere_for_test(g2_transform: Matrix4) -> Group {
+        let mut g2 = Group::new(g2_transform);
+        g2.add_child(Sphere::new(translation(5.0, 0.0, 0.0), Material::default()));
+
+        let mut g1 = Group::new(rotation_y(PI / 2.0));
+        g1.add_child(g2);
+        g1
+    }
+
+    // Children are boxed trait objects, so reach the nested sphere by shooting a ray through it
+    fn nested_sphere(g1: &Group) -> &dyn Shape {
+        let r = Ray::new(&point(0.0, 0.0, 100.0), &vector(0.0, 0.0, -1.0));
+        g1.intersect(&r)[0].shape
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        let g1 = nested_sphere_for_test(scaling(2.0, 2.0, 2.0));
+        let s = nested_sphere(&g1);
+
+        let p = s.world_to_object(point(-2.0, 0.0, -10.0));
+
+        assert_eq!(p, point(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn converting_a_normal_from_object_to_world_space() {
+        let g1 = nested_sphere_for_test(scaling(1.0, 2.0, 3.0));
+        let s = nested_sphere(&g1);
+        let sqrt3over3 = (3.0_f32).sqrt() / 3.0;
+
+        let n = s.normal_to_world(vector(sqrt3over3, sqrt3over3, sqrt3over3));
+
+        assert_eq!(n, vector(0.2857, 0.4286, -0.8571));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_child_object() {
+        let g1 = nested_sphere_for_test(scaling(1.0, 2.0, 3.0));
+        let s = nested_sphere(&g1);
+
+        let n = s.normal_at(point(1.7321, 1.1547, -5.5774));
+
+        assert!(n.eq(&vector(0.2857, 0.4286, -0.8571)));
+    }
+
+    #[test]
+    fn changing_the_transform_updates_the_children() {
+        let mut g = Group::default();
+        g.add_child(Sphere::default());
+
+        g.set_transform(translation(0.0, 0.0, 10.0));
+
+        let s = &g.children()[0];
+        assert_eq!(s.normal_at(point(0.0, 0.0, 9.0)), vector(0.0, 0.0, -1.0));
+        assert_eq!(
+            s.normal_at(point(0.0, FRAC_1_SQRT_2, 10.0 - FRAC_1_SQRT_2)),
+            vector(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2)
+        );
+    }
+
+    #[test]
+    fn a_group_has_a_bounding_box_that_contains_its_children() {
+        let s = Sphere::new(
+            translation(2.0, 5.0, -3.0) * scaling(2.0, 2.0, 2.0),
+            Material::default(),
+        );
+        let mut c = Cylinder::truncated(-2.0, 2.0, false);
+        c.set_transform(translation(-4.0, -1.0, 4.0) * scaling(0.5, 1.0, 0.5));
+        let mut g = Group::default();
+        g.add_child(s);
+        g.add_child(c);
+
+        let b = g.bounds();
+
+        assert_eq!(b.min, point(-4.5, -3.0, -5.0));
+        assert_eq!(b.max, point(4.0, 7.0, 4.5));
+    }
+
+    #[test]
+    fn intersecting_a_group_through_its_bvh() {
+        let mut g = Group::new(scaling(2.0, 2.0, 2.0));
+        for i in 0..20 {
+            let mut inner = Group::new(translation(i as f32 * 3.0, 0.0, 0.0));
+            inner.add_child(Sphere::default());
+            g.add_child(inner);
+        }
+        let r = Ray::new(&point(30.0, 0.0, -10.0), &vector(0.0, 0.0, 1.0));
+        let before = g.intersect(&r).len();
+
+        g.build_bvh(BvhSplit::Sah);
+        let xs = g.intersect(&r);
+
+        assert_eq!(before, 2);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].t, 8.0);
+    }
+
+    #[test]
+    fn a_rejected_transform_leaves_the_children_alone() {
+        let mut g = Group::default();
+        g.add_child(Sphere::default());
+        g.set_transform(translation(1.0, 0.0, 0.0));
+
+        assert_eq!(
+            g.try_set_transform(scaling(0.0, 0.0, 0.0)),
+            Err(NotInvertible)
+        );
+        assert_eq!(
+            *g.children()[0].parent_transform().matrix(),
+            translation(1.0, 0.0, 0.0)
+        );
+    }
+}
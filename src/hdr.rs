@@ -0,0 +1,697 @@
+//! High dynamic range file formats, which keep colors above 1.0 instead of clamping them:
+//! Radiance RGBE (`.hdr`) for reading and writing, and OpenEXR (`.exr`) for writing.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use flate2::{write::ZlibEncoder, Compression};
+
+use crate::{
+    canvas::{Canvas, CanvasError},
+    color::Color,
+    util::RtcFl,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExrCompression {
+    #[default]
+    None,
+    /// zlib over blocks of 16 scanlines, like OpenEXR's `ZIP_COMPRESSION`
+    Zip,
+}
+
+impl ExrCompression {
+    fn id(&self) -> u8 {
+        match self {
+            ExrCompression::None => 0,
+            ExrCompression::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(&self) -> usize {
+        match self {
+            ExrCompression::None => 1,
+            ExrCompression::Zip => 16,
+        }
+    }
+}
+
+/// The most pixels `from_hdr` accepts, 8192 by 8192.
+const MAX_HDR_PIXELS: usize = 1 << 26;
+
+impl Canvas {
+    /// Writes flat (not run-length encoded) Radiance RGBE. Negative components are
+    /// stored as 0, since the format has no sign.
+    pub fn write_hdr(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(
+            writer,
+            "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            self.height, self.width
+        )?;
+
+        let mut row = Vec::with_capacity(self.width * 4);
+        for y in 0..self.height {
+            row.clear();
+            for x in 0..self.width {
+                row.extend(to_rgbe(*self.pixel_at(x, y)));
+            }
+            writer.write_all(&row)?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn save_hdr(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_hdr(BufWriter::new(File::create(path)?))
+    }
+
+    /// Reads a Radiance RGBE file with flat or run-length encoded scanlines.
+    pub fn from_hdr(data: &[u8]) -> Result<Self, CanvasError> {
+        let mut lines = HeaderLines { data, position: 0 };
+
+        let magic = lines.next_line();
+        if magic != Some("#?RADIANCE") && magic != Some("#?RGBE") {
+            return Err(CanvasError::UnsupportedFormat(
+                magic.unwrap_or_default().to_string(),
+            ));
+        }
+
+        // Variables run until the first empty line
+        loop {
+            match lines.next_line() {
+                Some("") => break,
+                Some(line) if line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe" => {
+                    return Err(CanvasError::UnsupportedFormat(line.to_string()))
+                }
+                Some(_) => {}
+                None => return Err(CanvasError::InvalidHeader("missing resolution".to_string())),
+            }
+        }
+
+        let resolution = lines.next_line().unwrap_or_default();
+        let (width, height): (usize, usize) =
+            match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+                ["-Y", height, "+X", width] => match (width.parse(), height.parse()) {
+                    (Ok(width), Ok(height)) => (width, height),
+                    _ => return Err(invalid_resolution(resolution)),
+                },
+                _ => return Err(invalid_resolution(resolution)),
+            };
+
+        width
+            .checked_mul(height)
+            .filter(|&pixels| pixels <= MAX_HDR_PIXELS)
+            .ok_or_else(|| {
+                CanvasError::InvalidHeader(format!("{} by {} is too large", width, height))
+            })?;
+
+        // Run-length encoding lets a tiny file claim a huge image, so the canvas is only
+        // created once every scanline has actually decoded
+        let mut pixels = &data[lines.position..];
+        let mut colors = Vec::new();
+        for _ in 0..height {
+            let (scanline, rest) = read_scanline(pixels, width)?;
+            colors.extend(
+                scanline
+                    .chunks_exact(4)
+                    .map(|rgbe| from_rgbe([rgbe[0], rgbe[1], rgbe[2], rgbe[3]])),
+            );
+            pixels = rest;
+        }
+
+        let mut canvas = Canvas::new(width, height);
+        for (index, color) in colors.into_iter().enumerate() {
+            canvas.write_pixel(index % width, index / width, color);
+        }
+
+        Ok(canvas)
+    }
+
+    /// Writes a scanline OpenEXR file with 32-bit float R, G and B channels.
+    pub fn write_exr(&self, mut writer: impl Write, compression: ExrCompression) -> io::Result<()> {
+        let mut header = Vec::new();
+        header.extend([0x76, 0x2f, 0x31, 0x01]);
+        header.extend(2u32.to_le_bytes());
+
+        // Channels must be listed in alphabetical order
+        let mut channels = Vec::new();
+        for name in [b"B", b"G", b"R"] {
+            channels.extend(name);
+            channels.push(0);
+            channels.extend(2i32.to_le_bytes()); // FLOAT
+            channels.extend([0, 0, 0, 0]); // pLinear and reserved
+            channels.extend(1i32.to_le_bytes()); // xSampling
+            channels.extend(1i32.to_le_bytes()); // ySampling
+        }
+        channels.push(0);
+
+        let mut window = Vec::new();
+        for v in [0, 0, self.width as i32 - 1, self.height as i32 - 1] {
+            window.extend(v.to_le_bytes());
+        }
+
+        add_attribute(&mut header, "channels", "chlist", &channels);
+        add_attribute(
+            &mut header,
+            "compression",
+            "compression",
+            &[compression.id()],
+        );
+        add_attribute(&mut header, "dataWindow", "box2i", &window);
+        add_attribute(&mut header, "displayWindow", "box2i", &window);
+        add_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+        add_attribute(
+            &mut header,
+            "pixelAspectRatio",
+            "float",
+            &1.0f32.to_le_bytes(),
+        );
+        add_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+        add_attribute(
+            &mut header,
+            "screenWindowWidth",
+            "float",
+            &1.0f32.to_le_bytes(),
+        );
+        header.push(0);
+
+        let lines_per_block = compression.lines_per_block();
+        let mut chunks = Vec::new();
+        for first_line in (0..self.height).step_by(lines_per_block) {
+            let last_line = (first_line + lines_per_block).min(self.height);
+            let mut block = Vec::with_capacity((last_line - first_line) * self.width * 12);
+            for y in first_line..last_line {
+                for channel in [|c: &Color| c.blue, |c: &Color| c.green, |c: &Color| c.red] {
+                    for x in 0..self.width {
+                        block.extend(channel(self.pixel_at(x, y)).to_le_bytes());
+                    }
+                }
+            }
+
+            if compression == ExrCompression::Zip {
+                block = zip_block(&block)?;
+            }
+
+            let mut chunk = Vec::with_capacity(block.len() + 8);
+            chunk.extend((first_line as i32).to_le_bytes());
+            chunk.extend((block.len() as i32).to_le_bytes());
+            chunk.extend(block);
+            chunks.push(chunk);
+        }
+
+        writer.write_all(&header)?;
+        let mut offset = (header.len() + chunks.len() * 8) as u64;
+        for chunk in &chunks {
+            writer.write_all(&offset.to_le_bytes())?;
+            offset += chunk.len() as u64;
+        }
+        for chunk in &chunks {
+            writer.write_all(chunk)?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn save_exr(&self, path: impl AsRef<Path>, compression: ExrCompression) -> io::Result<()> {
+        self.write_exr(BufWriter::new(File::create(path)?), compression)
+    }
+}
+
+pub(crate) fn read_hdr_file(path: &Path) -> Result<Canvas, CanvasError> {
+    Canvas::from_hdr(&fs::read(path)?)
+}
+
+fn invalid_resolution(line: &str) -> CanvasError {
+    CanvasError::InvalidHeader(format!("unsupported resolution line: {}", line))
+}
+
+/// Newline separated header lines, tracking where the pixel data begins.
+struct HeaderLines<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> HeaderLines<'a> {
+    fn next_line(&mut self) -> Option<&'a str> {
+        let rest = self.data.get(self.position..)?;
+        let end = rest.iter().position(|&b| b == b'\n')?;
+        self.position += end + 1;
+
+        std::str::from_utf8(&rest[..end]).ok()
+    }
+}
+
+/// Splits one scanline of RGBE pixels off the front of `data`, decoding run-length
+/// encoding if the scanline uses it.
+fn read_scanline(data: &[u8], width: usize) -> Result<(Vec<u8>, &[u8]), CanvasError> {
+    let missing = |found: usize| CanvasError::MissingPixelData {
+        expected: width * 4,
+        found,
+    };
+
+    let is_rle = (8..32768).contains(&width)
+        && data.len() >= 4
+        && data[0] == 2
+        && data[1] == 2
+        && data[2] & 0x80 == 0;
+    if !is_rle {
+        if data.len() < width * 4 {
+            return Err(missing(data.len()));
+        }
+        return Ok((data[..width * 4].to_vec(), &data[width * 4..]));
+    }
+
+    // Each of the four components is run-length encoded separately
+    let mut components: Vec<Vec<u8>> = vec![Vec::new(); 4];
+    let mut position = 4;
+    for component in components.iter_mut() {
+        while component.len() < width {
+            let count = *data.get(position).ok_or_else(|| missing(position))? as usize;
+            position += 1;
+            if count > 128 {
+                let value = *data.get(position).ok_or_else(|| missing(position))?;
+                position += 1;
+                component.extend(std::iter::repeat_n(value, count - 128));
+            } else {
+                let values = data
+                    .get(position..position + count)
+                    .ok_or_else(|| missing(position))?;
+                position += count;
+                component.extend_from_slice(values);
+            }
+        }
+        component.truncate(width);
+    }
+
+    let scanline = (0..width)
+        .flat_map(|x| {
+            [
+                components[0][x],
+                components[1][x],
+                components[2][x],
+                components[3][x],
+            ]
+        })
+        .collect();
+
+    Ok((scanline, &data[position..]))
+}
+
+fn to_rgbe(color: Color) -> [u8; 4] {
+    // The largest value an exponent byte of 255 can hold. Infinities clamp to it and NaN to 0
+    let largest = 255.0 / 256.0 * 2f64.powi(127);
+    let component = |c: RtcFl| {
+        if c.is_nan() {
+            0.0
+        } else {
+            (c as f64).clamp(0.0, largest)
+        }
+    };
+    let (r, g, b) = (
+        component(color.red),
+        component(color.green),
+        component(color.blue),
+    );
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // v = mantissa * 2^exponent, with the mantissa in 0.5..1
+    let exponent = v.log2().floor() as i32 + 1;
+    let scale = 256.0 / 2f64.powi(exponent);
+
+    [
+        (r * scale).min(255.0) as u8,
+        (g * scale).min(255.0) as u8,
+        (b * scale).min(255.0) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+fn from_rgbe(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::black();
+    }
+
+    let f = 2f64.powi(rgbe[3] as i32 - (128 + 8));
+
+    Color::new(
+        ((rgbe[0] as f64 + 0.5) * f) as RtcFl,
+        ((rgbe[1] as f64 + 0.5) * f) as RtcFl,
+        ((rgbe[2] as f64 + 0.5) * f) as RtcFl,
+    )
+}
+
+fn add_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend(name.as_bytes());
+    header.push(0);
+    header.extend(kind.as_bytes());
+    header.push(0);
+    header.extend((value.len() as i32).to_le_bytes());
+    header.extend(value);
+}
+
+/// Compresses a block the way OpenEXR's ZIP codec expects: bytes split into even and
+/// odd halves, delta encoded, then zlib. Blocks that don't shrink are stored as is.
+fn zip_block(raw: &[u8]) -> io::Result<Vec<u8>> {
+    let half = raw.len().div_ceil(2);
+    let mut reordered = vec![0; raw.len()];
+    for (i, &byte) in raw.iter().enumerate() {
+        let index = if i % 2 == 0 { i / 2 } else { half + i / 2 };
+        reordered[index] = byte;
+    }
+
+    let mut previous = reordered.first().copied().unwrap_or_default();
+    for byte in reordered.iter_mut().skip(1) {
+        let current = *byte;
+        *byte = current.wrapping_sub(previous).wrapping_add(128);
+        previous = current;
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&reordered)?;
+    let compressed = encoder.finish()?;
+
+    Ok(if compressed.len() < raw.len() {
+        compressed
+    } else {
+        raw.to_vec()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::ZlibDecoder;
+
+    use crate::{
+        canvas::{Canvas, CanvasError},
+        color::Color,
+        hdr::{from_rgbe, to_rgbe, zip_block, ExrCompression},
+        util::RtcFl,
+    };
+
+    fn close(a: Color, b: Color) -> bool {
+        // RGBE shares one exponent between the components, so every component is only
+        // as precise as 8 bits of the brightest one
+        let tolerance = b.red.max(b.green).max(b.blue) / 128.0 + 1e-6;
+        let near = |x: RtcFl, y: RtcFl| (x - y).abs() <= tolerance;
+        near(a.red, b.red) && near(a.green, b.green) && near(a.blue, b.blue)
+    }
+
+    fn hdr_canvas_for_test() -> Canvas {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(0, 0, Color::new(0.25, 0.5, 1.0));
+        canvas.write_pixel(1, 0, Color::new(14.5, 3.0, 0.001));
+        canvas.write_pixel(2, 1, Color::new(1000.0, 0.0, 250.0));
+        canvas
+    }
+
+    #[test]
+    fn rgbe_keeps_values_above_one() {
+        for color in [
+            Color::new(0.25, 0.5, 1.0),
+            Color::new(14.5, 3.0, 0.01),
+            Color::new(1000.0, 0.0, 250.0),
+        ] {
+            assert!(close(from_rgbe(to_rgbe(color)), color));
+        }
+        assert_eq!(to_rgbe(Color::black()), [0, 0, 0, 0]);
+        assert_eq!(to_rgbe(Color::new(-1.0, 0.0, 0.0)), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rgbe_clamps_values_it_cannot_store() {
+        assert_eq!(
+            to_rgbe(Color::new(RtcFl::INFINITY, 1.0, 0.0)),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(to_rgbe(Color::new(RtcFl::MAX, 0.0, 0.0)), [255, 0, 0, 255]);
+        assert_eq!(
+            to_rgbe(Color::new(RtcFl::NAN, RtcFl::NEG_INFINITY, 0.5)),
+            to_rgbe(Color::new(0.0, 0.0, 0.5))
+        );
+
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(0, 0, Color::new(RtcFl::INFINITY, 0.0, 0.0));
+        let mut hdr = Vec::new();
+        canvas.write_hdr(&mut hdr).unwrap();
+        assert!(hdr.ends_with(&[255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn writing_and_reading_an_hdr_file() {
+        let canvas = hdr_canvas_for_test();
+        let mut hdr = Vec::new();
+
+        canvas.write_hdr(&mut hdr).unwrap();
+        let read_back = Canvas::from_hdr(&hdr).unwrap();
+
+        assert!(hdr.starts_with(b"#?RADIANCE\n"));
+        assert_eq!((read_back.width, read_back.height), (3, 2));
+        for y in 0..2 {
+            for x in 0..3 {
+                assert!(close(*read_back.pixel_at(x, y), *canvas.pixel_at(x, y)));
+            }
+        }
+    }
+
+    #[test]
+    fn reading_run_length_encoded_hdr_scanlines() {
+        let mut hdr = b"#?RADIANCE\n# made by hand\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+        hdr.extend([2, 2, 0, 8]);
+        // Red: a run of eight 128s. Green: eight literal values. Blue: two runs of four
+        hdr.extend([136, 128]);
+        hdr.extend([8, 0, 16, 32, 48, 64, 80, 96, 112]);
+        hdr.extend([132, 0, 132, 255]);
+        hdr.extend([136, 129]);
+
+        let canvas = Canvas::from_hdr(&hdr).unwrap();
+
+        assert!(close(*canvas.pixel_at(0, 0), from_rgbe([128, 0, 0, 129])));
+        assert!(close(*canvas.pixel_at(3, 0), from_rgbe([128, 48, 0, 129])));
+        assert!(close(
+            *canvas.pixel_at(7, 0),
+            from_rgbe([128, 112, 255, 129])
+        ));
+    }
+
+    #[test]
+    fn reading_a_broken_hdr_file_fails() {
+        assert!(matches!(
+            Canvas::from_hdr(b"P3\n1 1\n255\n0 0 0\n"),
+            Err(CanvasError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            Canvas::from_hdr(b"#?RADIANCE\n\n+X 2 -Y 1\n"),
+            Err(CanvasError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Canvas::from_hdr(b"#?RADIANCE\n\n-Y 1 +X 2\n\x80\x80\x80\x81"),
+            Err(CanvasError::MissingPixelData { .. })
+        ));
+    }
+
+    #[test]
+    fn reading_an_hdr_file_with_an_oversized_header_fails() {
+        assert!(matches!(
+            Canvas::from_hdr(b"#?RADIANCE\n\n-Y 100000 +X 100000\n"),
+            Err(CanvasError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Canvas::from_hdr(b"#?RADIANCE\n\n-Y 18446744073709551615 +X 2\n"),
+            Err(CanvasError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            Canvas::from_hdr(b"#?RADIANCE\n\n-Y 8000 +X 8000\n\x80\x80\x80\x81"),
+            Err(CanvasError::MissingPixelData { .. })
+        ));
+    }
+
+    fn le_u64(bytes: &[u8]) -> usize {
+        u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize
+    }
+
+    fn le_i32(bytes: &[u8]) -> usize {
+        i32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize
+    }
+
+    // Undoes what zip_block did to one block
+    fn unzip_block_for_test(data: &[u8], raw_size: usize) -> Vec<u8> {
+        if data.len() == raw_size {
+            return data.to_vec();
+        }
+
+        let mut reordered = Vec::new();
+        ZlibDecoder::new(data).read_to_end(&mut reordered).unwrap();
+        for i in 1..reordered.len() {
+            reordered[i] = reordered[i - 1]
+                .wrapping_add(reordered[i])
+                .wrapping_sub(128);
+        }
+
+        let half = reordered.len().div_ceil(2);
+        (0..reordered.len())
+            .map(|i| {
+                if i % 2 == 0 {
+                    reordered[i / 2]
+                } else {
+                    reordered[half + i / 2]
+                }
+            })
+            .collect()
+    }
+
+    // Reads back the float channels of a file written by write_exr
+    fn read_exr_for_test(exr: &[u8], width: usize, height: usize) -> Vec<Color> {
+        let mut position = 8;
+        let mut lines_per_block = 0;
+        loop {
+            let name_end = position + exr[position..].iter().position(|&b| b == 0).unwrap();
+            if name_end == position {
+                position += 1;
+                break;
+            }
+            let kind_end = name_end + 1 + exr[name_end + 1..].iter().position(|&b| b == 0).unwrap();
+            let size = le_i32(&exr[kind_end + 1..]);
+            let value = &exr[kind_end + 5..kind_end + 5 + size];
+            if &exr[position..name_end] == b"compression" {
+                lines_per_block = match value[0] {
+                    0 => 1,
+                    3 => 16,
+                    other => panic!("unexpected compression {}", other),
+                };
+            }
+            position = kind_end + 5 + size;
+        }
+
+        let mut pixels = vec![Color::black(); width * height];
+        for block in 0..height.div_ceil(lines_per_block) {
+            let offset = le_u64(&exr[position + block * 8..]);
+            let first_line = le_i32(&exr[offset..]);
+            let size = le_i32(&exr[offset + 4..]);
+            let lines = lines_per_block.min(height - first_line);
+            let raw = unzip_block_for_test(&exr[offset + 8..offset + 8 + size], lines * width * 12);
+
+            for line in 0..lines {
+                for x in 0..width {
+                    let sample = |channel: usize| {
+                        let at = (line * 3 + channel) * width * 4 + x * 4;
+                        f32::from_le_bytes(raw[at..at + 4].try_into().unwrap()) as RtcFl
+                    };
+                    pixels[(first_line + line) * width + x] =
+                        Color::new(sample(2), sample(1), sample(0));
+                }
+            }
+        }
+
+        pixels
+    }
+
+    #[test]
+    fn writing_exr_keeps_exact_float_values() {
+        let mut canvas = Canvas::new(5, 20);
+        for y in 0..20 {
+            for x in 0..5 {
+                let v = (x * 20 + y) as RtcFl;
+                canvas.write_pixel(x, y, Color::new(v * 1.5, -v, v / 7.0));
+            }
+        }
+
+        for compression in [ExrCompression::None, ExrCompression::Zip] {
+            let mut exr = Vec::new();
+            canvas.write_exr(&mut exr, compression).unwrap();
+
+            assert!(exr.starts_with(&[0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]));
+            let pixels = read_exr_for_test(&exr, 5, 20);
+            for y in 0..20 {
+                for x in 0..5 {
+                    let expected = *canvas.pixel_at(x, y);
+                    let actual = pixels[y * 5 + x];
+                    assert_eq!(actual.red.to_bits(), expected.red.to_bits());
+                    assert_eq!(actual.green.to_bits(), expected.green.to_bits());
+                    assert_eq!(actual.blue.to_bits(), expected.blue.to_bits());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exr_files_follow_the_openexr_layout() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(0, 0, Color::new(1.0, 0.5, 0.25));
+        canvas.write_pixel(1, 0, Color::new(2.0, 0.0, -1.0));
+        let mut exr = Vec::new();
+        canvas.write_exr(&mut exr, ExrCompression::None).unwrap();
+
+        // Magic number, then version 2 with no flags set: single part scanline file
+        let mut expected = b"\x76\x2f\x31\x01\x02\0\0\0".to_vec();
+        // Each attribute is name, type, size and value. B, G and R are FLOAT (2), not
+        // linear, sampled once per pixel
+        expected.extend(b"channels\0chlist\0\x37\0\0\0");
+        for name in [b"B", b"G", b"R"] {
+            expected.extend(name);
+            expected.extend(b"\0\x02\0\0\0\0\0\0\0\x01\0\0\0\x01\0\0\0");
+        }
+        expected.push(0);
+        expected.extend(b"compression\0compression\0\x01\0\0\0\0");
+        expected.extend(b"dataWindow\0box2i\0\x10\0\0\0");
+        expected.extend(b"\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0");
+        expected.extend(b"displayWindow\0box2i\0\x10\0\0\0");
+        expected.extend(b"\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0");
+        expected.extend(b"lineOrder\0lineOrder\0\x01\0\0\0\0");
+        expected.extend(b"pixelAspectRatio\0float\0\x04\0\0\0\0\0\x80\x3f");
+        expected.extend(b"screenWindowCenter\0v2f\0\x08\0\0\0\0\0\0\0\0\0\0\0");
+        expected.extend(b"screenWindowWidth\0float\0\x04\0\0\0\0\0\x80\x3f");
+        expected.push(0);
+        // One scanline, so the offset table holds a single offset just past itself
+        expected.extend((expected.len() as u64 + 8).to_le_bytes());
+        // Line 0, 24 bytes of data: the blue, green and red channel of the line in turn
+        expected.extend(b"\0\0\0\0\x18\0\0\0");
+        expected.extend(b"\0\0\x80\x3e\0\0\x80\xbf");
+        expected.extend(b"\0\0\0\x3f\0\0\0\0");
+        expected.extend(b"\0\0\x80\x3f\0\0\0\x40");
+
+        assert_eq!(exr, expected);
+    }
+
+    #[test]
+    fn zip_blocks_use_the_openexr_predictor() {
+        let raw = [0x10, 0x20, 0x11, 0x21].repeat(16);
+        let mut predicted = Vec::new();
+
+        let zipped = zip_block(&raw).unwrap();
+        ZlibDecoder::new(zipped.as_slice())
+            .read_to_end(&mut predicted)
+            .unwrap();
+
+        // Even bytes, then odd ones, each stored as the difference to the one before plus 128
+        let mut expected = vec![0x10];
+        expected.extend([0x81, 0x7f].repeat(15));
+        expected.extend([0x81, 0x8f]);
+        expected.extend([0x81, 0x7f].repeat(15));
+        expected.push(0x81);
+        assert_eq!(predicted, expected);
+    }
+
+    #[test]
+    fn zip_compression_makes_flat_images_smaller() {
+        let mut canvas = Canvas::new(64, 64);
+        canvas.write_rect(0, 0, 64, 64, Color::new(2.0, 1.0, 0.5));
+        let (mut plain, mut zipped) = (Vec::new(), Vec::new());
+
+        canvas.write_exr(&mut plain, ExrCompression::None).unwrap();
+        canvas.write_exr(&mut zipped, ExrCompression::Zip).unwrap();
+
+        assert!(zipped.len() * 10 < plain.len());
+        assert_eq!(
+            read_exr_for_test(&zipped, 64, 64)[64 * 64 - 1],
+            Color::new(2.0, 1.0, 0.5)
+        );
+    }
+}
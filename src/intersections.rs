@@ -7,11 +7,23 @@ use crate::{
 pub struct Intersection<'a> {
     pub t: RtcFl,
     pub shape: &'a dyn Shape,
+    /// Barycentric coordinates of the hit, only meaningful for triangles.
+    pub u: RtcFl,
+    pub v: RtcFl,
 }
 
 impl<'a> Intersection<'a> {
     pub fn new(t: RtcFl, shape: &'a dyn Shape) -> Self {
-        Self { t, shape }
+        Self {
+            t,
+            shape,
+            u: 0.0,
+            v: 0.0,
+        }
+    }
+
+    pub fn with_uv(t: RtcFl, shape: &'a dyn Shape, u: RtcFl, v: RtcFl) -> Self {
+        Self { t, shape, u, v }
     }
 }
 
@@ -49,15 +61,15 @@ impl<'a> Intersections<'a> {
 mod tests {
     use crate::color::Color;
     use crate::computation::Computation;
-    use crate::intersections::Intersection;
+    use crate::intersections::{Intersection, Intersections};
     use crate::light::Light;
     use crate::material::Material;
     use crate::ray::Ray;
-    use crate::sphere::Sphere;
+    use crate::sphere::{glass_sphere_for_test, Sphere};
     use crate::transformation::{scaling, translation};
     use crate::tuples::{point, vector};
     use crate::util::EPSILON;
-    use crate::world::{create_default_world_for_test, World};
+    use crate::world::{create_default_world_for_test, World, DEFAULT_MAX_DEPTH};
 
     #[test]
     fn shading_an_intersection() {
@@ -65,11 +77,11 @@ mod tests {
         let ray = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
 
         // The first object in the world
-        let shape = world.objects[0].as_ref();
+        let shape = world.objects()[0].as_ref();
         let i = Intersection::new(4.0, shape);
-        let comps = Computation::new(i, &ray);
+        let comps = Computation::new(i, &ray, &Intersections::new(vec![i]));
 
-        let c = world.shade_hit(comps);
+        let c = world.shade_hit(comps, DEFAULT_MAX_DEPTH);
 
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
@@ -82,11 +94,11 @@ mod tests {
         let ray = Ray::new(&point(0.0, 0.0, 0.0), &vector(0.0, 0.0, 1.0));
 
         // The second object in the world
-        let shape = world.objects[1].as_ref();
+        let shape = world.objects()[1].as_ref();
         let i = Intersection::new(0.5, shape);
-        let comps = Computation::new(i, &ray);
+        let comps = Computation::new(i, &ray, &Intersections::new(vec![i]));
 
-        let c = world.shade_hit(comps);
+        let c = world.shade_hit(comps, DEFAULT_MAX_DEPTH);
 
         assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
     }
@@ -95,7 +107,7 @@ mod tests {
     fn color_when_ray_misses() {
         let w = create_default_world_for_test();
         let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 1.0, 0.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r, DEFAULT_MAX_DEPTH);
 
         assert_eq!(c, Color::new(0.0, 0.0, 0.0));
     }
@@ -104,7 +116,7 @@ mod tests {
     fn color_when_ray_hits() {
         let w = create_default_world_for_test();
         let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r, DEFAULT_MAX_DEPTH);
 
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
@@ -119,8 +131,7 @@ mod tests {
         s1.material.specular = 0.2;
         s1.material.ambient = 1.0;
 
-        let mut s2 = Sphere::default();
-        s2.transform = scaling(0.5, 0.5, 0.5);
+        let mut s2 = Sphere::new(scaling(0.5, 0.5, 0.5), Material::default());
         s2.material.ambient = 1.0;
 
         let mut w = World::default();
@@ -129,9 +140,9 @@ mod tests {
         w.light.push(light);
 
         let r = Ray::new(&point(0.0, 0.0, 0.75), &vector(0.0, 0.0, -1.0));
-        let c = w.color_at(&r);
+        let c = w.color_at(&r, DEFAULT_MAX_DEPTH);
 
-        assert_eq!(c, w.objects[1].material().color);
+        assert_eq!(c, w.objects()[1].material().color);
     }
 
     #[test]
@@ -139,9 +150,21 @@ mod tests {
         let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
         let shape = Sphere::new(translation(0.0, 0.0, 1.0), Material::default());
         let i = Intersection::new(5.0, &shape);
-        let comps = Computation::new(i, &r);
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
 
         assert!(comps.over_point.z < -EPSILON / 2.0);
         assert!(comps.point.z > comps.over_point.z);
     }
+
+    #[test]
+    fn the_under_point_is_offset_below_the_surface() {
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let mut shape = glass_sphere_for_test();
+        shape.set_transform(translation(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
+
+        assert!(comps.under_point.z > EPSILON / 2.0);
+        assert!(comps.point.z < comps.under_point.z);
+    }
 }
@@ -1,21 +1,35 @@
 // Lib module loads all application modules.
 // Tests have been moved inside each module.
 // Keep alphabetical because CDO.
+pub mod antialias;
+pub mod bounds;
+pub mod bvh;
 pub mod camera;
 pub mod canvas;
 pub mod color;
 pub mod computation;
+pub mod cone;
+pub mod csg;
+pub mod cube;
+pub mod cylinder;
+pub mod group;
+pub mod hdr;
 pub mod intersections;
 pub mod light;
 pub mod material;
 pub mod matrix;
+pub mod noise;
+pub mod obj;
 pub mod pattern;
 pub mod plane;
 pub mod ray;
 pub mod render;
 pub mod shape;
 pub mod sphere;
+pub mod texture;
+pub mod tonemap;
 pub mod transformation;
+pub mod triangle;
 pub mod tuples;
 pub mod util;
 pub mod world;
@@ -1,20 +1,503 @@
-use crate::{color::Color, material::Material, shape::Shape, tuples::Tuple};
+use rand::Rng;
 
-#[derive(Copy, Clone)]
-pub struct Light {
-    pub position: Tuple,
-    pub intensity: Color,
+use crate::{
+    color::Color,
+    material::Material,
+    noise::SplitMix64,
+    shape::Shape,
+    tuples::{vector, Tuple},
+    util::{RtcFl, EPSILON, PI},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    Point(PointLight),
+    Area(AreaLight),
+    Spot(SpotLight),
+    Directional(DirectionalLight),
 }
 
 impl Light {
     pub fn point(position: Tuple, intensity: Color) -> Self {
+        Light::Point(PointLight {
+            position,
+            intensity,
+            attenuation: Attenuation::default(),
+        })
+    }
+
+    /// A rectangular light with one corner at `corner` and sides `full_uvec` and `full_vvec`,
+    /// split into `usteps` by `vsteps` cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `usteps` or `vsteps` is 0.
+    pub fn area(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        Light::Area(AreaLight::rectangle(
+            corner, full_uvec, usteps, full_vvec, vsteps, intensity,
+        ))
+    }
+
+    /// A spherical light of `radius` around `center`, sampled at `samples` points.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `samples` is 0.
+    pub fn sphere(center: Tuple, radius: RtcFl, samples: usize, intensity: Color) -> Self {
+        Light::Area(AreaLight::sphere(center, radius, samples, intensity))
+    }
+
+    /// A light at `position` shining along `direction`, fully bright within `inner_angle` of the
+    /// axis and dark beyond `outer_angle`. Both angles are in radians.
+    pub fn spot(
+        position: Tuple,
+        direction: Tuple,
+        inner_angle: RtcFl,
+        outer_angle: RtcFl,
+        intensity: Color,
+    ) -> Self {
+        Light::Spot(SpotLight::new(
+            position,
+            direction,
+            inner_angle,
+            outer_angle,
+            intensity,
+        ))
+    }
+
+    /// Light arriving from infinitely far away, travelling along `direction`, like sunlight.
+    pub fn directional(direction: Tuple, intensity: Color) -> Self {
+        Light::Directional(DirectionalLight::new(direction, intensity))
+    }
+
+    pub fn intensity(&self) -> Color {
+        match self {
+            Light::Point(l) => l.intensity,
+            Light::Area(l) => l.intensity,
+            Light::Spot(l) => l.intensity,
+            Light::Directional(l) => l.intensity,
+        }
+    }
+
+    /// Where the light is, or the middle of it for area lights. Directional lights have no position.
+    pub fn position(&self) -> Option<Tuple> {
+        match self {
+            Light::Point(l) => Some(l.position),
+            Light::Area(l) => Some(l.position),
+            Light::Spot(l) => Some(l.position),
+            Light::Directional(_) => None,
+        }
+    }
+
+    /// How the light fades with distance. Directional lights never fade.
+    pub fn attenuation(&self) -> Attenuation {
+        match self {
+            Light::Point(l) => l.attenuation,
+            Light::Area(l) => l.attenuation,
+            Light::Spot(l) => l.attenuation,
+            Light::Directional(_) => Attenuation::default(),
+        }
+    }
+
+    /// The same light, fading with distance as `attenuation` says. Ignored by directional lights,
+    /// which are infinitely far away.
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        match &mut self {
+            Light::Point(l) => l.attenuation = attenuation,
+            Light::Area(l) => l.attenuation = attenuation,
+            Light::Spot(l) => l.attenuation = attenuation,
+            Light::Directional(_) => {}
+        }
+        self
+    }
+
+    /// How much of the light is left by the time it gets from its middle to `point`.
+    pub fn attenuation_at(&self, point: Tuple) -> RtcFl {
+        match self.position() {
+            Some(position) => self.attenuation().factor((position - point).mag()),
+            None => 1.0,
+        }
+    }
+
+    /// How many directions `lighting` and shadow tests average over.
+    pub fn samples(&self) -> usize {
+        match self {
+            Light::Area(l) => l.samples(),
+            _ => 1,
+        }
+    }
+
+    /// The same light with a fresh set of jittered sample points. Lights that don't jitter come
+    /// back unchanged.
+    pub fn with_new_jitter(self) -> Self {
+        match self {
+            Light::Area(l) => Light::Area(l.with_new_jitter()),
+            _ => self,
+        }
+    }
+
+    /// Every direction light arrives at `point` from, one per sample.
+    pub fn samples_towards(&self, point: Tuple) -> impl Iterator<Item = LightSample> + '_ {
+        (0..self.samples()).map(move |i| self.sample_towards(i, point))
+    }
+
+    /// The `index`th direction light arrives at `point` from.
+    pub fn sample_towards(&self, index: usize, point: Tuple) -> LightSample {
+        let sample = match self {
+            Light::Point(l) => LightSample::between(point, l.position),
+            Light::Area(l) => LightSample::between(point, l.sample_point(index)),
+            Light::Spot(l) => {
+                let sample = LightSample::between(point, l.position);
+                LightSample {
+                    strength: l.cone_strength(-sample.direction),
+                    ..sample
+                }
+            }
+            Light::Directional(l) => LightSample {
+                direction: -l.direction,
+                distance: RtcFl::INFINITY,
+                strength: 1.0,
+            },
+        };
+
+        LightSample {
+            strength: sample.strength * self.attenuation().factor(sample.distance),
+            ..sample
+        }
+    }
+}
+
+/// One way light reaches a point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSample {
+    /// Unit vector from the point towards the light.
+    pub direction: Tuple,
+    /// How far away the light is. Infinite for directional lights.
+    pub distance: RtcFl,
+    /// The share of the light's intensity that arrives this way, after a spot light's cone and
+    /// attenuation have taken their cut.
+    pub strength: RtcFl,
+}
+
+impl LightSample {
+    fn between(point: Tuple, light_position: Tuple) -> Self {
+        let v = light_position - point;
+
+        Self {
+            direction: v.normalize(),
+            distance: v.mag(),
+            strength: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+    pub position: Tuple,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+}
+
+/// How light falls off as it travels away from its source.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AttenuationModel {
+    /// The same intensity at any distance, as in the book.
+    #[default]
+    None,
+    /// `1 / d²`, as real light behaves. Brighter than `intensity` closer than one unit.
+    InverseSquare,
+    /// `1 / (constant + linear * d + quadratic * d²)`.
+    Polynomial {
+        constant: RtcFl,
+        linear: RtcFl,
+        quadratic: RtcFl,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Attenuation {
+    pub model: AttenuationModel,
+    /// Beyond this distance the light contributes nothing and casts no shadow rays.
+    pub cutoff: Option<RtcFl>,
+}
+
+impl Attenuation {
+    pub fn inverse_square() -> Self {
+        Self {
+            model: AttenuationModel::InverseSquare,
+            cutoff: None,
+        }
+    }
+
+    pub fn polynomial(constant: RtcFl, linear: RtcFl, quadratic: RtcFl) -> Self {
+        Self {
+            model: AttenuationModel::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            },
+            cutoff: None,
+        }
+    }
+
+    pub fn with_cutoff(self, radius: RtcFl) -> Self {
+        Self {
+            cutoff: Some(radius),
+            ..self
+        }
+    }
+
+    /// How much of the light is left after travelling `distance`.
+    ///
+    /// The falloff is never divided by less than `EPSILON`, so a point right on the light gets a
+    /// large but finite factor instead of infinity.
+    pub fn factor(&self, distance: RtcFl) -> RtcFl {
+        if self.cutoff.is_some_and(|radius| distance > radius) {
+            return 0.0;
+        }
+
+        let falloff = match self.model {
+            AttenuationModel::None => return 1.0,
+            AttenuationModel::InverseSquare => distance * distance,
+            AttenuationModel::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => constant + linear * distance + quadratic * distance * distance,
+        };
+
+        1.0 / falloff.max(EPSILON)
+    }
+}
+
+/// The surface an area light is emitted from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AreaShape {
+    /// A parallelogram spanned by `uvec * usteps` and `vvec * vsteps`, sampled once per cell.
+    Rectangle {
+        corner: Tuple,
+        uvec: Tuple,
+        usteps: usize,
+        vvec: Tuple,
+        vsteps: usize,
+    },
+    /// A ball around the light's position, sampled at `samples` points spread over its surface.
+    Sphere { radius: RtcFl, samples: usize },
+}
+
+/// A light with a size, which casts soft shadows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AreaLight {
+    /// The middle of the light.
+    pub position: Tuple,
+    pub intensity: Color,
+    shape: AreaShape,
+    pub attenuation: Attenuation,
+    /// Pick a random spot within each sample's cell instead of its center. Trades banding for noise.
+    pub jitter: bool,
+    /// Picks the random spots, so every call sees the same ones until `with_new_jitter`.
+    seed: u64,
+}
+
+impl AreaLight {
+    /// # Panics
+    ///
+    /// Panics if `usteps` or `vsteps` is 0.
+    pub fn rectangle(
+        corner: Tuple,
+        full_uvec: Tuple,
+        usteps: usize,
+        full_vvec: Tuple,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        if usteps == 0 || vsteps == 0 {
+            panic!("An area light needs at least one step along each side");
+        }
+
+        Self {
+            position: corner + full_uvec / 2.0 + full_vvec / 2.0,
+            intensity,
+            shape: AreaShape::Rectangle {
+                corner,
+                uvec: full_uvec / usteps as RtcFl,
+                usteps,
+                vvec: full_vvec / vsteps as RtcFl,
+                vsteps,
+            },
+            attenuation: Attenuation::default(),
+            jitter: false,
+            seed: 0,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `samples` is 0.
+    pub fn sphere(center: Tuple, radius: RtcFl, samples: usize, intensity: Color) -> Self {
+        if samples == 0 {
+            panic!("A spherical light needs at least one sample");
+        }
+
+        Self {
+            position: center,
+            intensity,
+            shape: AreaShape::Sphere { radius, samples },
+            attenuation: Attenuation::default(),
+            jitter: false,
+            seed: 0,
+        }
+    }
+
+    /// The surface the light is emitted from. Only the constructors set it, so the step and sample
+    /// counts are never 0.
+    pub fn shape(&self) -> &AreaShape {
+        &self.shape
+    }
+
+    /// The same light with a fresh set of jittered sample points.
+    pub fn with_new_jitter(self) -> Self {
+        Self {
+            seed: rand::rng().random(),
+            ..self
+        }
+    }
+
+    pub fn samples(&self) -> usize {
+        match self.shape {
+            AreaShape::Rectangle { usteps, vsteps, .. } => usteps * vsteps,
+            AreaShape::Sphere { samples, .. } => samples,
+        }
+    }
+
+    /// Every sample point on the light. When `jitter` is set they move off their cell centers,
+    /// but stay put until `with_new_jitter` draws new ones.
+    pub fn sample_points(&self) -> impl Iterator<Item = Tuple> + '_ {
+        (0..self.samples()).map(|i| self.sample_point(i))
+    }
+
+    /// The `index`th sample point. Rectangle cells are numbered row by row along u.
+    pub fn sample_point(&self, index: usize) -> Tuple {
+        match self.shape {
+            AreaShape::Rectangle {
+                corner,
+                uvec,
+                usteps,
+                vvec,
+                ..
+            } => {
+                let u = (index % usteps) as RtcFl + self.offset(index, 0);
+                let v = (index / usteps) as RtcFl + self.offset(index, 1);
+
+                corner + uvec * u + vvec * v
+            }
+            AreaShape::Sphere { radius, samples } => {
+                // Equal-height bands have equal area on a sphere, so one point per band spreads
+                // them evenly. The golden angle keeps neighbouring bands from lining up.
+                let golden_angle = PI * (3.0 - (5.0 as RtcFl).sqrt());
+                let y = 1.0 - 2.0 * (index as RtcFl + self.offset(index, 0)) / samples as RtcFl;
+                let theta = if self.jitter {
+                    2.0 * PI * self.offset(index, 1)
+                } else {
+                    golden_angle * index as RtcFl
+                };
+                let r = (1.0 - y * y).max(0.0).sqrt();
+
+                self.position + vector(r * theta.cos(), y, r * theta.sin()) * radius
+            }
+        }
+    }
+
+    /// Where along `axis` of its cell the `index`th sample sits, from 0.0 to 1.0.
+    fn offset(&self, index: usize, axis: u64) -> RtcFl {
+        if self.jitter {
+            let bits = SplitMix64(self.seed ^ (index as u64 * 2 + axis)).next();
+            (bits >> 40) as RtcFl / (1u64 << 24) as RtcFl
+        } else {
+            0.5
+        }
+    }
+}
+
+/// A point light that only shines within a cone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpotLight {
+    pub position: Tuple,
+    /// Unit vector along the middle of the cone.
+    pub direction: Tuple,
+    /// Angle from the axis, in radians, within which the light is at full strength.
+    pub inner_angle: RtcFl,
+    /// Angle from the axis, in radians, beyond which there is no light at all.
+    pub outer_angle: RtcFl,
+    /// Shapes the fade between the two cones: 1.0 is linear in the cosine, higher is sharper.
+    pub falloff: RtcFl,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Tuple,
+        direction: Tuple,
+        inner_angle: RtcFl,
+        outer_angle: RtcFl,
+        intensity: Color,
+    ) -> Self {
         Self {
             position,
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle,
+            falloff: 1.0,
+            intensity,
+            attenuation: Attenuation::default(),
+        }
+    }
+
+    /// How much of the light travels along the unit vector `outgoing`.
+    pub fn cone_strength(&self, outgoing: Tuple) -> RtcFl {
+        let cos_angle = self.direction.dot(outgoing);
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+
+        if cos_angle >= cos_inner {
+            1.0
+        } else if cos_angle <= cos_outer {
+            0.0
+        } else {
+            ((cos_angle - cos_outer) / (cos_inner - cos_outer)).powf(self.falloff)
+        }
+    }
+}
+
+/// Parallel light from a source so far away that only its direction matters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+    /// Unit vector the light travels along.
+    pub direction: Tuple,
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Tuple, intensity: Color) -> Self {
+        Self {
+            direction: direction.normalize(),
             intensity,
         }
     }
 }
 
+/// Phong shading for `point` lit by `light`.
+///
+/// `light_intensity` is how much of the light reaches the point, from 0.0 in full shadow to 1.0
+/// when nothing is in the way. Diffuse and specular are averaged over the light's samples.
 pub fn lighting(
     material: &Material,
     object: &dyn Shape,
@@ -22,48 +505,50 @@ pub fn lighting(
     point: &Tuple,
     eye_vector: &Tuple,
     normal_vector: &Tuple,
-    in_shadow: bool,
+    light_intensity: RtcFl,
 ) -> Color {
     let effective_color = if let Some(pattern) = &material.pattern {
-        pattern.pattern_at_object(object, *point) * light.intensity
+        pattern.pattern_at_object(object, *point) * light.intensity()
     } else {
-        material.color * light.intensity
+        material.color * light.intensity()
     };
 
-    let light_vector = (light.position - *point).normalize();
-    let ambient = effective_color * material.ambient;
-    let light_dot_normal = light_vector.dot(*normal_vector);
+    // Ambient light fades too, so a local fill light doesn't brighten the whole scene
+    let ambient = effective_color * material.ambient * light.attenuation_at(*point);
 
-    let diffuse: Color;
-    let specular: Color;
+    // Nothing but ambient light reaches a point in full shadow
+    if light_intensity <= 0.0 {
+        return ambient;
+    }
+
+    let mut sum = Color::black();
+
+    for sample in light.samples_towards(*point) {
+        let light_vector = sample.direction;
+        let light_dot_normal = light_vector.dot(*normal_vector);
+
+        // Light is on the other side of the surface, or a spot light is pointing elsewhere
+        if light_dot_normal < 0.0 || sample.strength <= 0.0 {
+            continue;
+        }
 
-    if light_dot_normal < 0.0 {
-        // Light is on the other side of the surface
-        diffuse = Color::black();
-        specular = Color::black();
-    } else {
-        // Light is on the same side of the surface
         // Diffuse lighting is based on the angle between the light vector and the normal vector
-        diffuse = effective_color * material.diffuse * light_dot_normal;
+        let mut contribution = effective_color * material.diffuse * light_dot_normal;
 
         // Specular lighting is based on the angle between the reflection vector and the eye vector
         let reflect_vector = -light_vector.reflect(*normal_vector);
         let reflect_dot_eye = reflect_vector.dot(*eye_vector);
 
         // The reflect_dot_eye value is positive if the light reflects toward the eye
-        if reflect_dot_eye <= 0.0 {
-            specular = Color::black();
-        } else {
+        if reflect_dot_eye > 0.0 {
             let factor = reflect_dot_eye.powf(material.shininess);
-            specular = light.intensity * material.specular * factor;
+            contribution = contribution + light.intensity() * material.specular * factor;
         }
-    }
 
-    if in_shadow {
-        ambient
-    } else {
-        ambient + diffuse + specular
+        sum = sum + contribution * sample.strength;
     }
+
+    ambient + sum * (light_intensity / light.samples() as RtcFl)
 }
 
 #[cfg(test)]
@@ -73,9 +558,10 @@ mod tests {
     use crate::material::Material;
     use crate::{
         color::Color,
-        light::{lighting, Light},
+        light::{lighting, AreaLight, AreaShape, Attenuation, Light, SpotLight},
         sphere::Sphere,
         tuples::{point, vector},
+        util::{equal, RtcFl, PI},
     };
 
     #[test]
@@ -84,8 +570,8 @@ mod tests {
         let position = point(0.0, 0.0, 0.0);
         let light = Light::point(position, intensity);
 
-        assert!(light.position.eq(&position));
-        assert!(light.intensity.eq(&intensity));
+        assert_eq!(light.position(), Some(position));
+        assert!(light.intensity().eq(&intensity));
     }
 
     #[test]
@@ -103,7 +589,7 @@ mod tests {
             &position,
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
 
         //println!("{:?}", &result);
@@ -126,7 +612,7 @@ mod tests {
             &position,
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
 
         //println!("{:?}", &result);
@@ -149,7 +635,7 @@ mod tests {
             &position,
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
 
         //println!("{:?}", &result);
@@ -172,7 +658,7 @@ mod tests {
             &position,
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
 
         //println!("{:?}", &result);
@@ -194,7 +680,7 @@ mod tests {
             &position,
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
 
         //println!("{:?}", &result);
@@ -209,7 +695,6 @@ mod tests {
         let eyev = vector(0.0, 0.0, -1.0);
         let normalv = vector(0.0, 0.0, -1.0);
         let light = Light::point(point(0.0, 0.0, -10.0), Color::white());
-        let in_shadow = true;
         let result = lighting(
             &m,
             &Sphere::default(),
@@ -217,9 +702,384 @@ mod tests {
             &position,
             &eyev,
             &normalv,
-            in_shadow,
+            0.0,
         );
 
         assert!(Color::new(0.1, 0.1, 0.1).eq(&result));
     }
+
+    #[test]
+    fn lighting_scales_diffuse_and_specular_by_light_intensity() {
+        let m = Material {
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.0,
+            ..Default::default()
+        };
+        let position = point(0.0, 0.0, -1.0);
+        let eyev = vector(0.0, 0.0, -1.0);
+        let normalv = vector(0.0, 0.0, -1.0);
+        let light = Light::point(point(0.0, 0.0, -10.0), Color::white());
+        let examples = [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)];
+
+        for (intensity, expected) in examples {
+            let result = lighting(
+                &m,
+                &Sphere::default(),
+                &light,
+                &position,
+                &eyev,
+                &normalv,
+                intensity,
+            );
+
+            assert_eq!(result, Color::new(expected, expected, expected));
+        }
+    }
+
+    #[test]
+    fn creating_an_area_light() {
+        let light = AreaLight::rectangle(
+            point(0.0, 0.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            4,
+            vector(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+
+        assert_eq!(
+            *light.shape(),
+            AreaShape::Rectangle {
+                corner: point(0.0, 0.0, 0.0),
+                uvec: vector(0.5, 0.0, 0.0),
+                usteps: 4,
+                vvec: vector(0.0, 0.0, 0.5),
+                vsteps: 2,
+            }
+        );
+        assert_eq!(light.samples(), 8);
+        assert_eq!(light.position, point(1.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn finding_a_single_point_on_an_area_light() {
+        let light = AreaLight::rectangle(
+            point(0.0, 0.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            4,
+            vector(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+        let examples = [
+            (0, point(0.25, 0.0, 0.25)),
+            (1, point(0.75, 0.0, 0.25)),
+            (4, point(0.25, 0.0, 0.75)),
+            (2, point(1.25, 0.0, 0.25)),
+            (7, point(1.75, 0.0, 0.75)),
+        ];
+
+        for (index, expected) in examples {
+            assert_eq!(light.sample_point(index), expected);
+        }
+    }
+
+    #[test]
+    fn jittered_points_stay_inside_their_cell() {
+        let mut light = AreaLight::rectangle(
+            point(0.0, 0.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            4,
+            vector(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+        light.jitter = true;
+
+        for _ in 0..100 {
+            let p = light.with_new_jitter().sample_point(6);
+
+            assert!((1.0..=1.5).contains(&p.x), "x was {}", p.x);
+            assert!((0.5..=1.0).contains(&p.z), "z was {}", p.z);
+        }
+    }
+
+    #[test]
+    fn jittered_points_stay_put_until_redrawn() {
+        let mut light = AreaLight::rectangle(
+            point(0.0, 0.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            4,
+            vector(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+        let centers: Vec<_> = light.sample_points().collect();
+        light.jitter = true;
+        let light = light.with_new_jitter();
+        let first: Vec<_> = light.sample_points().collect();
+
+        assert_ne!(first, centers);
+        assert_eq!(light.sample_points().collect::<Vec<_>>(), first);
+
+        let redrawn: Vec<_> = light.with_new_jitter().sample_points().collect();
+        assert_ne!(redrawn, first);
+    }
+
+    #[test]
+    fn lights_that_dont_jitter_ignore_new_jitter() {
+        let area = Light::area(
+            point(0.0, 0.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            4,
+            vector(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+        let bulb = Light::point(point(0.0, 1.0, 0.0), Color::white());
+
+        assert_eq!(
+            area.with_new_jitter()
+                .samples_towards(point(0.0, -1.0, 0.0))
+                .collect::<Vec<_>>(),
+            area.samples_towards(point(0.0, -1.0, 0.0))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(bulb.with_new_jitter(), bulb);
+    }
+
+    #[test]
+    fn lighting_samples_the_area_light() {
+        let light = Light::area(
+            point(-0.5, -0.5, -5.0),
+            vector(1.0, 0.0, 0.0),
+            2,
+            vector(0.0, 1.0, 0.0),
+            2,
+            Color::white(),
+        );
+        let shape = Sphere::default();
+        let m = Material {
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.0,
+            ..Default::default()
+        };
+        let sqrt2over2 = 2.0_f32.sqrt() / 2.0;
+        let eye = point(0.0, 0.0, -5.0);
+        let examples = [
+            (point(0.0, 0.0, -1.0), 0.9965),
+            (point(0.0, sqrt2over2, -sqrt2over2), 0.62318),
+        ];
+
+        for (p, expected) in examples {
+            let eyev = (eye - p).normalize();
+            let normalv = vector(p.x, p.y, p.z);
+            let result = lighting(&m, &shape, &light, &p, &eyev, &normalv, 1.0);
+
+            assert_eq!(result, Color::new(expected, expected, expected));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "An area light needs at least one step along each side")]
+    fn an_area_light_without_steps_panics() {
+        Light::area(
+            point(0.0, 0.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            0,
+            vector(0.0, 0.0, 1.0),
+            2,
+            Color::white(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "A spherical light needs at least one sample")]
+    fn a_spherical_light_without_samples_panics() {
+        Light::sphere(point(0.0, 0.0, 0.0), 1.0, 0, Color::white());
+    }
+
+    #[test]
+    fn spherical_light_samples_lie_on_its_surface() {
+        let center = point(1.0, 2.0, 3.0);
+        let mut light = AreaLight::sphere(center, 0.5, 16, Color::white());
+
+        assert_eq!(light.samples(), 16);
+        for jitter in [false, true] {
+            light.jitter = jitter;
+            for p in light.sample_points() {
+                assert!(equal((p - center).mag(), 0.5));
+            }
+        }
+
+        // The samples should be spread around, not bunched on one side
+        light.jitter = false;
+        let mean = light
+            .sample_points()
+            .fold(vector(0.0, 0.0, 0.0), |sum, p| sum + (p - center))
+            / 16.0;
+        assert!(mean.mag() < 0.05, "mean offset was {:?}", mean);
+    }
+
+    #[test]
+    fn a_spot_light_fades_between_its_cones() {
+        let light = SpotLight::new(
+            point(0.0, 0.0, 0.0),
+            vector(0.0, 0.0, 2.0),
+            PI / 6.0,
+            PI / 3.0,
+            Color::white(),
+        );
+        let towards = |angle: RtcFl| vector(angle.sin(), 0.0, angle.cos());
+
+        assert_eq!(light.direction, vector(0.0, 0.0, 1.0));
+        assert_eq!(light.cone_strength(towards(0.0)), 1.0);
+        assert_eq!(light.cone_strength(towards(PI / 6.0)), 1.0);
+        assert!(equal(light.cone_strength(towards(PI / 4.0)), 0.56583));
+        assert_eq!(light.cone_strength(towards(PI / 3.0)), 0.0);
+        assert_eq!(light.cone_strength(towards(PI / 2.0)), 0.0);
+    }
+
+    #[test]
+    fn a_higher_falloff_darkens_the_edge_of_a_spot_light() {
+        let mut light = SpotLight::new(
+            point(0.0, 0.0, 0.0),
+            vector(0.0, 0.0, 1.0),
+            PI / 6.0,
+            PI / 3.0,
+            Color::white(),
+        );
+        light.falloff = 2.0;
+        let outgoing = vector((PI / 4.0).sin(), 0.0, (PI / 4.0).cos());
+
+        assert!(equal(light.cone_strength(outgoing), 0.32016));
+    }
+
+    #[test]
+    fn lighting_with_a_spot_light() {
+        let m = Material::default();
+        let eyev = vector(0.0, 0.0, -1.0);
+        let normalv = vector(0.0, 0.0, -1.0);
+        let light = Light::spot(
+            point(0.0, 0.0, -10.0),
+            vector(0.0, 0.0, 1.0),
+            PI / 36.0,
+            PI / 18.0,
+            Color::white(),
+        );
+        let lit = |position| {
+            lighting(
+                &m,
+                &Sphere::default(),
+                &light,
+                &position,
+                &eyev,
+                &normalv,
+                1.0,
+            )
+        };
+
+        assert_eq!(lit(point(0.0, 0.0, 0.0)), Color::new(1.9, 1.9, 1.9));
+        assert_eq!(lit(point(5.0, 0.0, 0.0)), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn lighting_with_a_directional_light() {
+        let m = Material::default();
+        let sqrt2over2 = 2.0_f32.sqrt() / 2.0;
+        let eyev = vector(0.0, -sqrt2over2, -sqrt2over2);
+        let normalv = vector(0.0, 0.0, -1.0);
+        let light = Light::directional(vector(0.0, -1.0, 1.0), Color::white());
+
+        let sample = light.sample_towards(0, point(0.0, 0.0, 0.0));
+        assert_eq!(sample.direction, vector(0.0, sqrt2over2, -sqrt2over2));
+        assert_eq!(sample.distance, RtcFl::INFINITY);
+        assert_eq!(light.position(), None);
+
+        // The same as a point light in that direction, wherever the point is
+        for position in [point(0.0, 0.0, 0.0), point(100.0, -50.0, 20.0)] {
+            let result = lighting(
+                &m,
+                &Sphere::default(),
+                &light,
+                &position,
+                &eyev,
+                &normalv,
+                1.0,
+            );
+
+            assert_eq!(result, Color::new(1.6364, 1.6364, 1.6364));
+        }
+    }
+
+    #[test]
+    fn attenuation_models() {
+        let none = Attenuation::default();
+        let inverse_square = Attenuation::inverse_square();
+        let polynomial = Attenuation::polynomial(1.0, 0.5, 0.25);
+
+        assert_eq!(none.factor(1000.0), 1.0);
+        assert_eq!(inverse_square.factor(2.0), 0.25);
+        assert_eq!(inverse_square.factor(0.5), 4.0);
+        assert!(equal(polynomial.factor(2.0), 1.0 / 3.0));
+        assert_eq!(polynomial.factor(0.0), 1.0);
+    }
+
+    #[test]
+    fn attenuation_stays_finite_on_top_of_the_light() {
+        let inverse_square = Attenuation::inverse_square().factor(0.0);
+        let linear = Attenuation::polynomial(0.0, 1.0, 0.0).factor(0.0);
+
+        assert!(inverse_square.is_finite() && inverse_square > 0.0);
+        assert!(linear.is_finite() && linear > 0.0);
+    }
+
+    #[test]
+    fn nothing_reaches_past_the_cutoff() {
+        let attenuation = Attenuation::polynomial(1.0, 0.5, 0.25).with_cutoff(5.0);
+
+        assert!(equal(attenuation.factor(4.0), 1.0 / 7.0));
+        assert!(attenuation.factor(5.0) > 0.0);
+        assert_eq!(attenuation.factor(5.01), 0.0);
+        assert_eq!(Attenuation::default().with_cutoff(5.0).factor(6.0), 0.0);
+    }
+
+    #[test]
+    fn lighting_with_an_inverse_square_light() {
+        let m = Material::default();
+        let position = point(0.0, 0.0, 0.0);
+        let eyev = vector(0.0, 0.0, -1.0);
+        let normalv = vector(0.0, 0.0, -1.0);
+        let lit_from = |z| {
+            let light = Light::point(point(0.0, 0.0, z), Color::white())
+                .with_attenuation(Attenuation::inverse_square());
+            lighting(
+                &m,
+                &Sphere::default(),
+                &light,
+                &position,
+                &eyev,
+                &normalv,
+                1.0,
+            )
+        };
+
+        assert_eq!(lit_from(-1.0), Color::new(1.9, 1.9, 1.9));
+        assert_eq!(lit_from(-2.0), Color::new(0.475, 0.475, 0.475));
+        assert_eq!(lit_from(-4.0), Color::new(0.11875, 0.11875, 0.11875));
+    }
+
+    #[test]
+    fn directional_lights_ignore_attenuation() {
+        let light = Light::directional(vector(0.0, -1.0, 0.0), Color::white())
+            .with_attenuation(Attenuation::inverse_square().with_cutoff(1.0));
+
+        assert_eq!(light.attenuation(), Attenuation::default());
+        assert_eq!(
+            light.sample_towards(0, point(0.0, -100.0, 0.0)).strength,
+            1.0
+        );
+    }
 }
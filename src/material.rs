@@ -8,6 +8,12 @@ pub struct Material {
     pub diffuse: RtcFl,
     pub specular: RtcFl,
     pub shininess: RtcFl,
+    /// How much of the surrounding scene the surface mirrors, from 0 (none) to 1 (a perfect mirror).
+    pub reflective: RtcFl,
+    /// How much light passes through the surface, from 0 (opaque) to 1 (fully transparent).
+    pub transparency: RtcFl,
+    /// How much light bends entering the material: 1.0 for a vacuum, about 1.5 for glass.
+    pub refractive_index: RtcFl,
 }
 
 impl Material {
@@ -26,6 +32,9 @@ impl Material {
             diffuse,
             specular,
             shininess,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
         }
     }
 }
@@ -39,6 +48,9 @@ impl Default for Material {
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
         }
     }
 }
@@ -68,6 +80,9 @@ mod tests {
         assert_eq!(m.diffuse, diffuse);
         assert_eq!(m.specular, specular);
         assert_eq!(m.shininess, shininess);
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
     }
 
     #[test]
@@ -95,7 +110,7 @@ mod tests {
             &point(0.9, 0.0, 0.0),
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
         let c2 = lighting(
             &m,
@@ -104,7 +119,7 @@ mod tests {
             &point(1.1, 0.0, 0.0),
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
 
         assert_eq!(c1, Color::white());
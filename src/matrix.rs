@@ -1,4 +1,4 @@
-use std::ops;
+use std::{error::Error, fmt, ops};
 use nalgebra::{SMatrix, Vector4};
 use crate::{
     transformation::Transformation,
@@ -105,6 +105,69 @@ impl Operations<Matrix4> for Matrix4 {
     }
 }
 
+/// Returned when a transform is set to a matrix that has no inverse, such as a scaling by zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotInvertible;
+
+impl fmt::Display for NotInvertible {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transform matrix cannot be inverted")
+    }
+}
+
+impl Error for NotInvertible {}
+
+/// A transform together with its inverse and the transpose of that inverse.
+///
+/// Rays are transformed by the inverse and normals by its transpose, so both are worked
+/// out once when the transform is set instead of for every ray. This also means a matrix
+/// that can't be inverted is rejected up front rather than halfway through a render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedTransform {
+    matrix: Matrix4,
+    inverse: Matrix4,
+    inverse_transpose: Matrix4,
+}
+
+impl Default for CachedTransform {
+    fn default() -> Self {
+        Self {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+            inverse_transpose: Matrix4::identity(),
+        }
+    }
+}
+
+impl CachedTransform {
+    /// Panics if the matrix can't be inverted; use `try_new` to check first.
+    pub fn new(matrix: Matrix4) -> Self {
+        Self::try_new(matrix).expect("Cannot invert this transform.")
+    }
+
+    pub fn try_new(matrix: Matrix4) -> Result<Self, NotInvertible> {
+        let inverse = matrix.try_inverse().ok_or(NotInvertible)?;
+
+        Ok(Self {
+            matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        })
+    }
+
+    pub fn matrix(&self) -> &Matrix4 {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Matrix4 {
+        &self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> &Matrix4 {
+        &self.inverse_transpose
+    }
+}
+
 pub fn view_transform(from: Tuple, to: Tuple, up: Tuple) -> Matrix4 {
     let forward = (to - from).normalize();
 
@@ -555,4 +618,31 @@ mod tests {
 
         assert!(ma.equals(actual));
     }
+
+    #[test]
+    fn a_cached_transform_holds_its_inverse() {
+        let m = Transformation::new()
+            .scaling(2.0, 4.0, 8.0)
+            .translation(1.0, 2.0, 3.0)
+            .get();
+
+        let t = CachedTransform::new(m);
+
+        assert_eq!(*t.matrix(), m);
+        assert!(t.inverse().equals(m.try_inverse().unwrap()));
+        assert!(t.inverse_transpose().equals(m.try_inverse().unwrap().transpose()));
+    }
+
+    #[test]
+    fn a_non_invertible_transform_is_rejected() {
+        let m = Transformation::new().scaling(1.0, 0.0, 1.0).get();
+
+        assert_eq!(CachedTransform::try_new(m), Err(NotInvertible));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot invert this transform.")]
+    fn creating_a_cached_transform_from_a_non_invertible_matrix_panics() {
+        CachedTransform::new(Matrix4::zeros());
+    }
 }
@@ -0,0 +1,229 @@
+use crate::{
+    tuples::{vector, Tuple},
+    util::RtcFl,
+};
+
+/// SplitMix64, a tiny generator that is plenty for shuffling a 256 entry table or placing
+/// light samples.
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+impl SplitMix64 {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Ken Perlin's improved gradient noise in 3D.
+///
+/// The permutation table is shuffled from `seed` with a generator of our own, so the same
+/// seed always gives the same noise, whatever version of `rand` we build against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Perlin {
+    seed: u64,
+    permutation: Vec<u8>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        let mut rng = SplitMix64(seed);
+
+        // Fisher-Yates
+        for i in (1..table.len()).rev() {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+
+        // Doubled so lookups can run past 255 without wrapping
+        let permutation = table.iter().chain(table.iter()).copied().collect();
+
+        Self { seed, permutation }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Noise at `point`, roughly in -1..1 and 0 at every integer lattice point.
+    pub fn noise(&self, point: Tuple) -> RtcFl {
+        let (xi, x) = split(point.x);
+        let (yi, y) = split(point.y);
+        let (zi, z) = split(point.z);
+
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = |i: usize| self.permutation[i] as usize;
+        let a = p(xi) + yi;
+        let aa = p(a) + zi;
+        let ab = p(a + 1) + zi;
+        let b = p(xi + 1) + yi;
+        let ba = p(b) + zi;
+        let bb = p(b + 1) + zi;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p(aa), x, y, z), grad(p(ba), x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(p(ab), x, y - 1.0, z),
+                    grad(p(bb), x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p(aa + 1), x, y, z - 1.0),
+                    grad(p(ba + 1), x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(p(ab + 1), x, y - 1.0, z - 1.0),
+                    grad(p(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    /// Fractal Brownian motion: `octaves` layers of noise, each at twice the
+    /// frequency and half the amplitude of the one before.
+    pub fn fbm(&self, point: Tuple, octaves: usize) -> RtcFl {
+        self.octaves(point, octaves, |n| n)
+    }
+
+    /// Like `fbm`, but summing the absolute value of each layer, which gives
+    /// sharp creases where the noise crosses zero.
+    pub fn turbulence(&self, point: Tuple, octaves: usize) -> RtcFl {
+        self.octaves(point, octaves, RtcFl::abs)
+    }
+
+    fn octaves(&self, point: Tuple, octaves: usize, f: impl Fn(RtcFl) -> RtcFl) -> RtcFl {
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+
+        for _ in 0..octaves {
+            total += f(self.noise(point * frequency)) * amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+
+        total
+    }
+
+    /// Three decorrelated `fbm` values, for displacing a point in every direction.
+    pub fn fbm_vector(&self, point: Tuple, octaves: usize) -> Tuple {
+        // Sampling far apart in noise space keeps the three axes from moving together
+        vector(
+            self.fbm(point, octaves),
+            self.fbm(point + vector(31.416, 47.853, 12.679), octaves),
+            self.fbm(point + vector(-23.172, 8.931, 59.614), octaves),
+        )
+    }
+}
+
+/// The lattice cell (wrapped to the permutation table) and offset within it.
+fn split(coordinate: RtcFl) -> (usize, RtcFl) {
+    let floor = coordinate.floor();
+
+    ((floor as i64).rem_euclid(256) as usize, coordinate - floor)
+}
+
+fn fade(t: RtcFl) -> RtcFl {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: RtcFl, a: RtcFl, b: RtcFl) -> RtcFl {
+    a + t * (b - a)
+}
+
+/// Dot product of the offset with one of 12 edge gradients picked by `hash`.
+fn grad(hash: usize, x: RtcFl, y: RtcFl, z: RtcFl) -> RtcFl {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        noise::{Perlin, SplitMix64},
+        tuples::point,
+        util::RtcFl,
+    };
+
+    #[test]
+    fn noise_is_zero_on_lattice_points() {
+        let perlin = Perlin::new(7);
+
+        assert_eq!(perlin.noise(point(0.0, 0.0, 0.0)), 0.0);
+        assert_eq!(perlin.noise(point(3.0, -2.0, 17.0)), 0.0);
+        assert_eq!(perlin.noise(point(-300.0, 256.0, 1.0)), 0.0);
+    }
+
+    #[test]
+    fn noise_is_deterministic_for_a_seed() {
+        let p = point(1.3, -4.7, 0.25);
+
+        assert_eq!(Perlin::new(42).noise(p), Perlin::new(42).noise(p));
+        assert_ne!(Perlin::new(42).noise(p), Perlin::new(43).noise(p));
+    }
+
+    #[test]
+    fn the_shuffle_is_pinned_to_our_own_generator() {
+        // Reference output of SplitMix64 seeded with 0
+        let mut rng = SplitMix64(0);
+        assert_eq!(rng.next(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next(), 0x6e78_9e6a_a1b9_65f4);
+
+        // If these change, every saved scene using noise renders differently
+        assert_eq!(
+            Perlin::new(42).permutation[..8],
+            [203, 217, 124, 199, 53, 101, 223, 240]
+        );
+    }
+
+    #[test]
+    fn noise_stays_within_range() {
+        let perlin = Perlin::new(1);
+
+        for i in 0..1000 {
+            let f = i as RtcFl;
+            let n = perlin.noise(point(f * 0.137, f * -0.071, f * 0.029));
+
+            assert!((-1.0..=1.0).contains(&n));
+        }
+    }
+
+    #[test]
+    fn turbulence_is_never_negative() {
+        let perlin = Perlin::new(1);
+
+        for i in 0..100 {
+            let f = i as RtcFl;
+            assert!(perlin.turbulence(point(f * 0.31, f * 0.17, -f * 0.23), 4) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn one_octave_of_fbm_is_plain_noise() {
+        let perlin = Perlin::new(5);
+        let p = point(0.4, 0.9, -2.2);
+
+        assert_eq!(perlin.fbm(p, 1), perlin.noise(p));
+    }
+}
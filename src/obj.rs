@@ -0,0 +1,407 @@
+use std::{fs, io, path::Path};
+
+use crate::{
+    group::Group,
+    material::Material,
+    shape::Shape,
+    triangle::{SmoothTriangle, Triangle},
+    tuples::{point, vector, Tuple},
+    util::RtcFl,
+};
+
+/// A single triangle read from an OBJ file, with vertex normals if the file had them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Face {
+    pub vertices: [Tuple; 3],
+    pub normals: Option<[Tuple; 3]>,
+}
+
+impl Face {
+    pub fn to_shape(&self, material: &Material) -> Box<dyn Shape> {
+        let [p1, p2, p3] = self.vertices;
+
+        match self.normals {
+            Some([n1, n2, n3]) => {
+                let mut t = SmoothTriangle::new(p1, p2, p3, n1, n2, n3);
+                t.material = material.clone();
+                Box::new(t)
+            }
+            None => {
+                let mut t = Triangle::new(p1, p2, p3);
+                t.material = material.clone();
+                Box::new(t)
+            }
+        }
+    }
+}
+
+/// Parses the subset of Wavefront OBJ we care about: vertices (`v`), vertex normals (`vn`),
+/// faces (`f`) and named groups (`g`). Anything else is kept in `ignored_lines`.
+#[derive(Debug, Default)]
+pub struct ObjParser {
+    pub vertices: Vec<Tuple>,
+    pub normals: Vec<Tuple>,
+    pub default_group: Vec<Face>,
+    /// Named groups in the order they first appear in the file.
+    pub groups: Vec<(String, Vec<Face>)>,
+    pub ignored_lines: Vec<String>,
+}
+
+impl ObjParser {
+    pub fn parse(content: &str) -> Self {
+        let mut parser = Self::default();
+        let mut current_group: Option<String> = None;
+
+        for line in content.lines() {
+            let parsed = match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["v", x, y, z, ..] => {
+                    parse_coords(x, y, z).map(|(x, y, z)| parser.vertices.push(point(x, y, z)))
+                }
+                ["vn", x, y, z, ..] => {
+                    parse_coords(x, y, z).map(|(x, y, z)| parser.normals.push(vector(x, y, z)))
+                }
+                ["f", refs @ ..] if refs.len() >= 3 => {
+                    parser.parse_face(refs).map(|faces| match &current_group {
+                        Some(name) => parser.group_mut(name).extend(faces),
+                        None => parser.default_group.extend(faces),
+                    })
+                }
+                ["g", name, ..] => {
+                    current_group = Some(name.to_string());
+                    parser.group_mut(name);
+                    Some(())
+                }
+                [] => Some(()),
+                _ => None,
+            };
+
+            if parsed.is_none() {
+                parser.ignored_lines.push(line.to_string());
+            }
+        }
+
+        parser
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(Self::parse(&content))
+    }
+
+    /// Faces in a named group, if the file had one by that name.
+    pub fn group(&self, name: &str) -> Option<&[Face]> {
+        self.groups
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, faces)| faces.as_slice())
+    }
+
+    /// The faces of group `name`, adding it at the end if this is its first mention.
+    fn group_mut(&mut self, name: &str) -> &mut Vec<Face> {
+        let index = match self.groups.iter().position(|(n, _)| n == name) {
+            Some(index) => index,
+            None => {
+                self.groups.push((name.to_string(), Vec::new()));
+                self.groups.len() - 1
+            }
+        };
+
+        &mut self.groups[index].1
+    }
+
+    /// Every face in the file as a shape ready for `World::add_boxed_objects`.
+    pub fn triangles(&self, material: &Material) -> Vec<Box<dyn Shape>> {
+        self.default_group
+            .iter()
+            .chain(self.groups.iter().flat_map(|(_, faces)| faces))
+            .map(|face| face.to_shape(material))
+            .collect()
+    }
+
+    /// The whole file as a single group, with each named group as a subgroup of its own.
+    pub fn to_group(&self, material: &Material) -> Group {
+        let mut group = Group::default();
+        group.add_boxed_children(
+            self.default_group
+                .iter()
+                .map(|f| f.to_shape(material))
+                .collect(),
+        );
+
+        for (_, faces) in &self.groups {
+            let mut sub = Group::default();
+            sub.add_boxed_children(faces.iter().map(|f| f.to_shape(material)).collect());
+            group.add_child(sub);
+        }
+
+        group
+    }
+
+    /// Turns a polygon into a fan of triangles around its first vertex.
+    fn parse_face(&self, refs: &[&str]) -> Option<Vec<Face>> {
+        let mut vertices = Vec::with_capacity(refs.len());
+        let mut normals = Vec::with_capacity(refs.len());
+
+        for r in refs {
+            // Vertex references are `v`, `v/vt`, `v//vn` or `v/vt/vn`. We have no use for `vt`.
+            let mut parts = r.split('/');
+            let v = lookup(&self.vertices, parts.next()?)?;
+            let n = match parts.nth(1) {
+                Some(idx) if !idx.is_empty() => Some(lookup(&self.normals, idx)?),
+                _ => None,
+            };
+
+            vertices.push(v);
+            normals.push(n);
+        }
+
+        let faces = (1..vertices.len() - 1)
+            .map(|i| {
+                let normals = match (normals[0], normals[i], normals[i + 1]) {
+                    (Some(n1), Some(n2), Some(n3)) => Some([n1, n2, n3]),
+                    _ => None,
+                };
+
+                Face {
+                    vertices: [vertices[0], vertices[i], vertices[i + 1]],
+                    normals,
+                }
+            })
+            .collect();
+
+        Some(faces)
+    }
+}
+
+fn parse_coords(x: &str, y: &str, z: &str) -> Option<(RtcFl, RtcFl, RtcFl)> {
+    Some((x.parse().ok()?, y.parse().ok()?, z.parse().ok()?))
+}
+
+/// OBJ indices start at 1.
+fn lookup(list: &[Tuple], index: &str) -> Option<Tuple> {
+    let i: usize = index.parse().ok()?;
+    list.get(i.checked_sub(1)?).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        material::Material,
+        obj::ObjParser,
+        ray::Ray,
+        tuples::{point, vector},
+        world::World,
+    };
+
+    #[test]
+    fn ignoring_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright\n\
+                         who traveled much faster than light.\n\
+                         She set out one day\n\
+                         in a relative way,\n\
+                         and came back the previous night.";
+
+        let parser = ObjParser::parse(gibberish);
+
+        assert_eq!(parser.ignored_lines.len(), 5);
+    }
+
+    #[test]
+    fn vertex_records() {
+        let file = "v -1 1 0\n\
+                    v -1.0000 0.5000 0.0000\n\
+                    v 1 0 0\n\
+                    v 1 1 0";
+
+        let parser = ObjParser::parse(file);
+
+        assert_eq!(parser.vertices[0], point(-1.0, 1.0, 0.0));
+        assert_eq!(parser.vertices[1], point(-1.0, 0.5, 0.0));
+        assert_eq!(parser.vertices[2], point(1.0, 0.0, 0.0));
+        assert_eq!(parser.vertices[3], point(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn parsing_triangle_faces() {
+        let file = "v -1 1 0\n\
+                    v -1 0 0\n\
+                    v 1 0 0\n\
+                    v 1 1 0\n\
+                    \n\
+                    f 1 2 3\n\
+                    f 1 3 4";
+
+        let parser = ObjParser::parse(file);
+        let g = &parser.default_group;
+
+        assert_eq!(g.len(), 2);
+        assert_eq!(
+            g[0].vertices,
+            [parser.vertices[0], parser.vertices[1], parser.vertices[2]]
+        );
+        assert_eq!(
+            g[1].vertices,
+            [parser.vertices[0], parser.vertices[2], parser.vertices[3]]
+        );
+    }
+
+    #[test]
+    fn triangulating_polygons() {
+        let file = "v -1 1 0\n\
+                    v -1 0 0\n\
+                    v 1 0 0\n\
+                    v 1 1 0\n\
+                    v 0 2 0\n\
+                    \n\
+                    f 1 2 3 4 5";
+
+        let parser = ObjParser::parse(file);
+        let g = &parser.default_group;
+        let v = &parser.vertices;
+
+        assert_eq!(g.len(), 3);
+        assert_eq!(g[0].vertices, [v[0], v[1], v[2]]);
+        assert_eq!(g[1].vertices, [v[0], v[2], v[3]]);
+        assert_eq!(g[2].vertices, [v[0], v[3], v[4]]);
+    }
+
+    #[test]
+    fn triangles_in_groups() {
+        let file = "v -1 1 0\n\
+                    v -1 0 0\n\
+                    v 1 0 0\n\
+                    v 1 1 0\n\
+                    \n\
+                    g FirstGroup\n\
+                    f 1 2 3\n\
+                    g SecondGroup\n\
+                    f 1 3 4";
+
+        let parser = ObjParser::parse(file);
+        let v = &parser.vertices;
+        let g1 = parser.group("FirstGroup").unwrap();
+        let g2 = parser.group("SecondGroup").unwrap();
+
+        assert!(parser.default_group.is_empty());
+        assert_eq!(g1[0].vertices, [v[0], v[1], v[2]]);
+        assert_eq!(g2[0].vertices, [v[0], v[2], v[3]]);
+    }
+
+    #[test]
+    fn groups_keep_the_order_of_the_file() {
+        let file = "v -1 1 0\n\
+                    v -1 0 0\n\
+                    v 1 0 0\n\
+                    v 1 1 0\n\
+                    \n\
+                    g Zebra\n\
+                    f 1 2 3\n\
+                    g Apple\n\
+                    f 1 3 4\n\
+                    g Mango\n\
+                    g Zebra\n\
+                    f 2 3 4";
+
+        let parser = ObjParser::parse(file);
+        let names: Vec<_> = parser
+            .groups
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        assert_eq!(names, ["Zebra", "Apple", "Mango"]);
+        assert_eq!(parser.group("Zebra").unwrap().len(), 2);
+        assert!(parser.group("Mango").unwrap().is_empty());
+    }
+
+    #[test]
+    fn vertex_normal_records() {
+        let file = "vn 0 0 1\n\
+                    vn 0.707 0 -0.707\n\
+                    vn 1 2 3";
+
+        let parser = ObjParser::parse(file);
+
+        assert_eq!(parser.normals[0], vector(0.0, 0.0, 1.0));
+        assert_eq!(parser.normals[1], vector(0.707, 0.0, -0.707));
+        assert_eq!(parser.normals[2], vector(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn faces_with_normals() {
+        let file = "v 0 1 0\n\
+                    v -1 0 0\n\
+                    v 1 0 0\n\
+                    \n\
+                    vn -1 0 0\n\
+                    vn 1 0 0\n\
+                    vn 0 1 0\n\
+                    \n\
+                    f 1//3 2//1 3//2\n\
+                    f 1/0/3 2/102/1 3/14/2";
+
+        let parser = ObjParser::parse(file);
+        let g = &parser.default_group;
+        let v = &parser.vertices;
+        let n = &parser.normals;
+
+        assert_eq!(g.len(), 2);
+        assert_eq!(g[0].vertices, [v[0], v[1], v[2]]);
+        assert_eq!(g[0].normals, Some([n[2], n[0], n[1]]));
+        assert_eq!(g[1], g[0]);
+    }
+
+    #[test]
+    fn faces_referring_to_missing_vertices_are_ignored() {
+        let file = "v 0 1 0\n\
+                    v -1 0 0\n\
+                    f 1 2 3";
+
+        let parser = ObjParser::parse(file);
+
+        assert!(parser.default_group.is_empty());
+        assert_eq!(parser.ignored_lines, vec!["f 1 2 3".to_string()]);
+    }
+
+    #[test]
+    fn converting_an_obj_file_to_a_group() {
+        let file = "v -1 1 0\n\
+                    v -1 0 0\n\
+                    v 1 0 0\n\
+                    v 1 1 0\n\
+                    \n\
+                    g FirstGroup\n\
+                    f 1 2 3\n\
+                    g SecondGroup\n\
+                    f 1 3 4";
+
+        let parser = ObjParser::parse(file);
+        let g = parser.to_group(&Material::default());
+
+        assert_eq!(g.children().len(), 2);
+
+        let mut w = World::default();
+        w.add_object(g);
+        let r = Ray::new(&point(0.5, 0.5, -2.0), &vector(0.0, 0.0, 1.0));
+
+        assert_eq!(w.intersect(&r).hit().unwrap().t, 2.0);
+    }
+
+    #[test]
+    fn triangles_can_be_added_to_a_world() {
+        let file = "v 0 1 0\n\
+                    v -1 0 0\n\
+                    v 1 0 0\n\
+                    f 1 2 3";
+
+        let parser = ObjParser::parse(file);
+        let mut w = World::default();
+        w.add_boxed_objects(parser.triangles(&Material::default()));
+
+        let r = Ray::new(&point(0.0, 0.5, -2.0), &vector(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+
+        assert_eq!(w.objects().len(), 1);
+        assert_eq!(xs.hit().unwrap().t, 2.0);
+    }
+}
@@ -1,87 +1,338 @@
-use crate::{color::Color, matrix::Matrix4, shape::Shape, tuples::Tuple};
+use crate::{
+    color::Color,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
+    noise::Perlin,
+    shape::Shape,
+    texture::ImagePattern,
+    transformation::scaling,
+    tuples::Tuple,
+    util::RtcFl,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Pattern {
     Stripe(StripePattern),
-    // Gradient(GradientPattern),
-    // Ring(RingPattern),
-    // Checker(CheckerPattern),
+    Gradient(GradientPattern),
+    Ring(RingPattern),
+    Checker(CheckerPattern),
+    RadialGradient(RadialGradientPattern),
+    Blend(BlendPattern),
+    Perturbed(PerturbedPattern),
+    Image(ImagePattern),
 }
 
 impl Pattern {
     pub fn pattern_at(&self, point: Tuple) -> Color {
         match self {
             Pattern::Stripe(p) => p.pattern_at(point),
-            // Match other patterns
+            Pattern::Gradient(p) => p.pattern_at(point),
+            Pattern::Ring(p) => p.pattern_at(point),
+            Pattern::Checker(p) => p.pattern_at(point),
+            Pattern::RadialGradient(p) => p.pattern_at(point),
+            Pattern::Blend(p) => p.pattern_at(point),
+            Pattern::Perturbed(p) => p.pattern_at(point),
+            Pattern::Image(p) => p.pattern_at(point),
+        }
+    }
+
+    pub fn transform(&self) -> &CachedTransform {
+        match self {
+            Pattern::Stripe(p) => p.transform(),
+            Pattern::Gradient(p) => p.transform(),
+            Pattern::Ring(p) => p.transform(),
+            Pattern::Checker(p) => p.transform(),
+            Pattern::RadialGradient(p) => p.transform(),
+            Pattern::Blend(p) => p.transform(),
+            Pattern::Perturbed(p) => p.transform(),
+            Pattern::Image(p) => p.transform(),
+        }
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        match self {
+            Pattern::Stripe(p) => p.set_transform(transform),
+            Pattern::Gradient(p) => p.set_transform(transform),
+            Pattern::Ring(p) => p.set_transform(transform),
+            Pattern::Checker(p) => p.set_transform(transform),
+            Pattern::RadialGradient(p) => p.set_transform(transform),
+            Pattern::Blend(p) => p.set_transform(transform),
+            Pattern::Perturbed(p) => p.set_transform(transform),
+            Pattern::Image(p) => p.set_transform(transform),
+        }
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        match self {
+            Pattern::Stripe(p) => p.try_set_transform(transform),
+            Pattern::Gradient(p) => p.try_set_transform(transform),
+            Pattern::Ring(p) => p.try_set_transform(transform),
+            Pattern::Checker(p) => p.try_set_transform(transform),
+            Pattern::RadialGradient(p) => p.try_set_transform(transform),
+            Pattern::Blend(p) => p.try_set_transform(transform),
+            Pattern::Perturbed(p) => p.try_set_transform(transform),
+            Pattern::Image(p) => p.try_set_transform(transform),
         }
     }
 
     pub fn pattern_at_object(&self, shape: &dyn Shape, world_point: Tuple) -> Color {
-        let object_point = shape
-            .transform()
-            .try_inverse()
-            .expect("Shape transform must be invertible for pattern calculation")
-            * world_point;
-
-        let pattern_point = match self {
-            Pattern::Stripe(p) => {
-                p.transform
-                    .try_inverse()
-                    .expect("Pattern Transform must be invertible for pattern calculation")
-                    * object_point
-            }
-        };
+        let object_point = shape.world_to_object(world_point);
+        let pattern_point = *self.transform().inverse() * object_point;
 
         self.pattern_at(pattern_point)
     }
 }
 
+/// What fills one of a pattern's bands: either a plain color, or another pattern
+/// with its own transform relative to the pattern that holds it.
 #[derive(Debug, Clone, PartialEq)]
-pub struct StripePattern {
-    pub a: Color,
-    pub b: Color,
-    pub transform: Matrix4,
+pub enum PatternSlot {
+    Solid(Color),
+    Nested(Box<Pattern>),
 }
 
-impl StripePattern {
-    pub fn new(a: Color, b: Color) -> Self {
-        Self {
-            a,
-            b,
-            transform: Matrix4::identity(),
+impl PatternSlot {
+    /// The color at `point`, given in the space of the pattern holding this slot.
+    pub fn color_at(&self, point: Tuple) -> Color {
+        match self {
+            PatternSlot::Solid(color) => *color,
+            PatternSlot::Nested(pattern) => {
+                pattern.pattern_at(*pattern.transform().inverse() * point)
+            }
         }
     }
+}
 
-    pub fn set_transform(&mut self, transform: Matrix4) {
-        self.transform = transform;
+impl From<Color> for PatternSlot {
+    fn from(color: Color) -> Self {
+        PatternSlot::Solid(color)
+    }
+}
+
+impl From<Pattern> for PatternSlot {
+    fn from(pattern: Pattern) -> Self {
+        PatternSlot::Nested(Box::new(pattern))
     }
+}
+
+/// Declares a pattern that picks between, or mixes, two slots `a` and `b`. Each one only
+/// needs its own `pattern_at` on top.
+macro_rules! two_slot_pattern {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name {
+            pub a: PatternSlot,
+            pub b: PatternSlot,
+            transform: CachedTransform,
+        }
+
+        impl $name {
+            pub fn new(a: impl Into<PatternSlot>, b: impl Into<PatternSlot>) -> Self {
+                Self {
+                    a: a.into(),
+                    b: b.into(),
+                    transform: CachedTransform::default(),
+                }
+            }
 
+            pub fn transform(&self) -> &CachedTransform {
+                &self.transform
+            }
+
+            /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+            pub fn set_transform(&mut self, transform: Matrix4) {
+                self.transform = CachedTransform::new(transform);
+            }
+
+            pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+                self.transform = CachedTransform::try_new(transform)?;
+                Ok(())
+            }
+        }
+    };
+}
+
+two_slot_pattern!(
+    /// Stripes of `a` and `b` along x, one unit wide.
+    StripePattern
+);
+
+impl StripePattern {
     fn pattern_at(&self, point: Tuple) -> Color {
         if (point.x.floor().abs() as usize).is_multiple_of(2) {
-            self.a
+            self.a.color_at(point)
         } else {
-            self.b
+            self.b.color_at(point)
         }
     }
 }
 
+two_slot_pattern!(
+    /// Blends linearly from `a` to `b` along x, starting over at every whole number.
+    GradientPattern
+);
+
+impl GradientPattern {
+    fn pattern_at(&self, point: Tuple) -> Color {
+        let fraction = point.x - point.x.floor();
+
+        let (a, b) = (self.a.color_at(point), self.b.color_at(point));
+
+        a + (b - a) * fraction
+    }
+}
+
+two_slot_pattern!(
+    /// Concentric rings around the y axis, alternating between `a` and `b`.
+    RingPattern
+);
+
+impl RingPattern {
+    fn pattern_at(&self, point: Tuple) -> Color {
+        let distance = (point.x.powi(2) + point.z.powi(2)).sqrt();
+
+        if (distance.floor() as usize).is_multiple_of(2) {
+            self.a.color_at(point)
+        } else {
+            self.b.color_at(point)
+        }
+    }
+}
+
+two_slot_pattern!(
+    /// Alternating unit cubes of `a` and `b`, like a 3D checkerboard.
+    CheckerPattern
+);
+
+impl CheckerPattern {
+    fn pattern_at(&self, point: Tuple) -> Color {
+        let sum = point.x.floor() + point.y.floor() + point.z.floor();
+
+        if (sum as i64).rem_euclid(2) == 0 {
+            self.a.color_at(point)
+        } else {
+            self.b.color_at(point)
+        }
+    }
+}
+
+two_slot_pattern!(
+    /// Like `RingPattern`, but blending from `a` to `b` across each ring instead of
+    /// switching between them.
+    RadialGradientPattern
+);
+
+impl RadialGradientPattern {
+    fn pattern_at(&self, point: Tuple) -> Color {
+        let distance = (point.x.powi(2) + point.z.powi(2)).sqrt();
+        let fraction = distance - distance.floor();
+
+        let (a, b) = (self.a.color_at(point), self.b.color_at(point));
+
+        a + (b - a) * fraction
+    }
+}
+
+two_slot_pattern!(
+    /// The average of `a` and `b` at every point.
+    BlendPattern
+);
+
+impl BlendPattern {
+    fn pattern_at(&self, point: Tuple) -> Color {
+        (self.a.color_at(point) + self.b.color_at(point)) * 0.5
+    }
+}
+
+/// Jitters each lookup point with noise before handing it to `pattern`, so
+/// straight bands come out wavy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerturbedPattern {
+    pub pattern: Box<Pattern>,
+    pub noise: Perlin,
+    /// How far, at most, a point gets pushed
+    pub scale: RtcFl,
+    pub octaves: usize,
+    transform: CachedTransform,
+}
+
+impl PerturbedPattern {
+    pub fn new(pattern: Pattern, scale: RtcFl, seed: u64) -> Self {
+        Self {
+            pattern: Box::new(pattern),
+            noise: Perlin::new(seed),
+            scale,
+            octaves: 3,
+            transform: CachedTransform::default(),
+        }
+    }
+
+    /// Thin, swirling veins of `b` through `a`.
+    pub fn marble(a: Color, b: Color, seed: u64) -> Self {
+        let mut veins = Pattern::Stripe(StripePattern::new(a, b));
+        veins.set_transform(scaling(0.2, 0.2, 0.2));
+
+        Self {
+            octaves: 6,
+            ..Self::new(veins, 0.6, seed)
+        }
+    }
+
+    /// Growth rings around the y axis, slightly warped like real grain.
+    pub fn wood(a: Color, b: Color, seed: u64) -> Self {
+        let mut rings = Pattern::Ring(RingPattern::new(a, b));
+        rings.set_transform(scaling(0.1, 1.0, 0.1));
+
+        Self::new(rings, 0.05, seed)
+    }
+
+    pub fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
+
+    fn pattern_at(&self, point: Tuple) -> Color {
+        let jittered = point + self.noise.fbm_vector(point, self.octaves) * self.scale;
+
+        self.pattern
+            .pattern_at(*self.pattern.transform().inverse() * jittered)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use crate::{
+        canvas::Canvas,
         color::Color,
         material::Material,
-        pattern::{Pattern, StripePattern},
+        matrix::NotInvertible,
+        pattern::{
+            BlendPattern, CheckerPattern, GradientPattern, Pattern, PerturbedPattern,
+            RadialGradientPattern, RingPattern, StripePattern,
+        },
         sphere::Sphere,
-        transformation::Transformation,
+        texture::{ImagePattern, UvMapping},
+        transformation::{rotation_y, scaling, translation, Transformation},
         tuples::point,
+        util::{RtcFl, PI},
     };
 
     #[test]
     fn creating_stripe_pattern() {
         let pattern = StripePattern::new(Color::white(), Color::black());
-        assert!(pattern.a == Color::white());
-        assert!(pattern.b == Color::black());
+        assert!(pattern.a == Color::white().into());
+        assert!(pattern.b == Color::black().into());
     }
 
     #[test]
@@ -164,4 +415,237 @@ mod tests {
 
         assert_eq!(c, Color::white());
     }
+
+    #[test]
+    fn gradient_linearly_interpolates_between_colors() {
+        let pattern = GradientPattern::new(Color::white(), Color::black());
+
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 0.0)), Color::white());
+        assert_eq!(
+            pattern.pattern_at(point(0.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(
+            pattern.pattern_at(point(0.5, 0.0, 0.0)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.pattern_at(point(0.75, 0.0, 0.0)),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn ring_should_extend_in_both_x_and_z() {
+        let pattern = RingPattern::new(Color::white(), Color::black());
+
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.pattern_at(point(1.0, 0.0, 0.0)), Color::black());
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 1.0)), Color::black());
+        // 0.708 = just slightly more than sqrt(2)/2
+        assert_eq!(pattern.pattern_at(point(0.708, 0.0, 0.708)), Color::black());
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_x() {
+        let pattern = CheckerPattern::new(Color::white(), Color::black());
+
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.pattern_at(point(0.99, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.pattern_at(point(1.01, 0.0, 0.0)), Color::black());
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_y() {
+        let pattern = CheckerPattern::new(Color::white(), Color::black());
+
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.pattern_at(point(0.0, 0.99, 0.0)), Color::white());
+        assert_eq!(pattern.pattern_at(point(0.0, 1.01, 0.0)), Color::black());
+    }
+
+    #[test]
+    fn checkers_should_repeat_in_z() {
+        let pattern = CheckerPattern::new(Color::white(), Color::black());
+
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 0.99)), Color::white());
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 1.01)), Color::black());
+    }
+
+    #[test]
+    fn checkers_alternate_across_negative_coordinates() {
+        let pattern = CheckerPattern::new(Color::white(), Color::black());
+
+        assert_eq!(pattern.pattern_at(point(-0.5, 0.0, 0.0)), Color::black());
+        assert_eq!(pattern.pattern_at(point(-1.5, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.pattern_at(point(-0.5, -0.5, 0.0)), Color::white());
+    }
+
+    #[test]
+    fn radial_gradient_interpolates_with_distance_from_the_y_axis() {
+        let pattern = RadialGradientPattern::new(Color::white(), Color::black());
+
+        assert_eq!(pattern.pattern_at(point(0.0, 0.0, 0.0)), Color::white());
+        assert_eq!(
+            pattern.pattern_at(point(0.0, 5.0, 0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(pattern.pattern_at(point(0.6, 0.0, 0.8)), Color::white());
+        assert_eq!(
+            pattern.pattern_at(point(-1.25, 0.0, 0.0)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+    }
+
+    #[test]
+    fn every_pattern_is_moved_by_its_own_transform() {
+        let object = Sphere::default();
+        let patterns = [
+            Pattern::Stripe(StripePattern::new(Color::white(), Color::black())),
+            Pattern::Gradient(GradientPattern::new(Color::white(), Color::black())),
+            Pattern::Ring(RingPattern::new(Color::white(), Color::black())),
+            Pattern::Checker(CheckerPattern::new(Color::white(), Color::black())),
+            Pattern::RadialGradient(RadialGradientPattern::new(Color::white(), Color::black())),
+        ];
+
+        for mut pattern in patterns {
+            let untransformed = pattern.pattern_at_object(&object, point(0.25, 0.0, 0.0));
+            pattern.set_transform(translation(1.25, 0.0, 0.0));
+
+            assert_eq!(
+                pattern.pattern_at_object(&object, point(1.5, 0.0, 0.0)),
+                untransformed
+            );
+        }
+    }
+
+    #[test]
+    fn stripes_can_be_made_of_other_patterns() {
+        let red = Color::new(1.0, 0.0, 0.0);
+        let checkers = Pattern::Checker(CheckerPattern::new(Color::white(), Color::black()));
+        let pattern = StripePattern::new(checkers, red);
+
+        assert_eq!(pattern.pattern_at(point(0.5, 0.0, 0.0)), Color::white());
+        assert_eq!(pattern.pattern_at(point(0.5, 1.5, 0.0)), Color::black());
+        assert_eq!(pattern.pattern_at(point(1.5, 0.0, 0.0)), red);
+        assert_eq!(pattern.pattern_at(point(1.5, 1.5, 0.0)), red);
+    }
+
+    #[test]
+    fn nested_patterns_apply_their_own_transform_on_top_of_the_parent() {
+        let mut checkers = Pattern::Checker(CheckerPattern::new(Color::white(), Color::black()));
+        checkers.set_transform(scaling(0.5, 0.5, 0.5));
+        let mut pattern = Pattern::Stripe(StripePattern::new(checkers, Color::black()));
+        pattern.set_transform(scaling(4.0, 4.0, 4.0));
+        let object = Sphere::default();
+
+        // Stripe space is a quarter of object space, and checker space is double stripe space
+        assert_eq!(
+            pattern.pattern_at_object(&object, point(1.0, 0.0, 0.0)),
+            Color::white()
+        );
+        assert_eq!(
+            pattern.pattern_at_object(&object, point(3.0, 0.0, 0.0)),
+            Color::black()
+        );
+        assert_eq!(
+            pattern.pattern_at_object(&object, point(3.0, 2.0, 0.0)),
+            Color::white()
+        );
+    }
+
+    #[test]
+    fn blend_averages_two_patterns() {
+        let stripes = Pattern::Stripe(StripePattern::new(Color::white(), Color::black()));
+        let mut crossed = stripes.clone();
+        crossed.set_transform(rotation_y(PI / 2.0));
+        // The crossed stripes run along z, alternating as z goes negative
+        let pattern = BlendPattern::new(stripes, crossed);
+
+        assert_eq!(pattern.pattern_at(point(0.5, 0.0, -0.5)), Color::white());
+        assert_eq!(
+            pattern.pattern_at(point(1.5, 0.0, -0.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            pattern.pattern_at(point(0.5, 0.0, -1.5)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(pattern.pattern_at(point(1.5, 0.0, -1.5)), Color::black());
+    }
+
+    #[test]
+    fn blend_of_plain_colors() {
+        let pattern = BlendPattern::new(Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0));
+
+        assert_eq!(
+            pattern.pattern_at(point(3.0, -2.0, 7.0)),
+            Color::new(0.5, 0.0, 0.5)
+        );
+    }
+
+    #[test]
+    fn perturbing_by_nothing_leaves_the_pattern_alone() {
+        let mut stripes = Pattern::Stripe(StripePattern::new(Color::white(), Color::black()));
+        stripes.set_transform(scaling(0.5, 0.5, 0.5));
+        let pattern = PerturbedPattern::new(stripes.clone(), 0.0, 1);
+
+        for x in [0.1, 0.4, 0.6, 0.9, 1.3] {
+            let p = point(x, 0.3, -0.2);
+
+            assert_eq!(
+                pattern.pattern_at(p),
+                stripes.pattern_at(*stripes.transform().inverse() * p)
+            );
+        }
+    }
+
+    #[test]
+    fn a_pattern_transform_that_cannot_be_inverted_is_rejected() {
+        let image = ImagePattern::new(Canvas::new(1, 1), UvMapping::Planar);
+        let mut patterns = [
+            Pattern::Stripe(StripePattern::new(Color::white(), Color::black())),
+            Pattern::Checker(CheckerPattern::new(Color::white(), Color::black())),
+            Pattern::Blend(BlendPattern::new(Color::white(), Color::black())),
+            Pattern::Perturbed(PerturbedPattern::wood(Color::white(), Color::black(), 1)),
+            Pattern::Image(image),
+        ];
+
+        for pattern in patterns.iter_mut() {
+            pattern.set_transform(translation(1.0, 2.0, 3.0));
+
+            assert_eq!(
+                pattern.try_set_transform(scaling(0.0, 1.0, 1.0)),
+                Err(NotInvertible)
+            );
+            assert_eq!(*pattern.transform().matrix(), translation(1.0, 2.0, 3.0));
+            assert_eq!(pattern.try_set_transform(scaling(2.0, 2.0, 2.0)), Ok(()));
+        }
+    }
+
+    #[test]
+    fn perturbing_moves_the_bands() {
+        let stripes = Pattern::Gradient(GradientPattern::new(Color::white(), Color::black()));
+        let pattern = PerturbedPattern::new(stripes.clone(), 0.5, 1);
+        let p = point(0.3, 0.6, 0.2);
+
+        assert_ne!(pattern.pattern_at(p), stripes.pattern_at(p));
+    }
+
+    #[test]
+    fn perturbed_patterns_are_deterministic_for_a_seed() {
+        let a = PerturbedPattern::marble(Color::white(), Color::black(), 9);
+        let b = PerturbedPattern::marble(Color::white(), Color::black(), 9);
+        let c = PerturbedPattern::wood(Color::white(), Color::black(), 9);
+        let d = PerturbedPattern::wood(Color::white(), Color::black(), 9);
+
+        for i in 0..50 {
+            let f = i as RtcFl * 0.173;
+            let p = point(f, -f * 0.5, f * 1.7);
+
+            assert_eq!(a.pattern_at(p), b.pattern_at(p));
+            assert_eq!(c.pattern_at(p), d.pattern_at(p));
+        }
+    }
 }
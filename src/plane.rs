@@ -1,17 +1,19 @@
 use crate::{
+    bounds::BoundingBox,
     intersections::Intersection,
     material::Material,
-    matrix::Matrix4,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
     ray::Ray,
     shape::{next_shape_id, Shape},
-    tuples::{vector, Tuple},
-    util::EPSILON,
+    tuples::{point, vector, Tuple},
+    util::{RtcFl, EPSILON},
 };
 
 #[derive(Debug, Clone)]
 pub struct Plane {
     pub id: usize,
-    pub transform: Matrix4,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
     pub material: Material,
 }
 
@@ -19,7 +21,8 @@ impl Default for Plane {
     fn default() -> Self {
         Self {
             id: next_shape_id(),
-            transform: Matrix4::identity(),
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
             material: Material::default(),
         }
     }
@@ -35,34 +38,62 @@ impl Plane {
     pub fn new(transform: Matrix4, material: Material) -> Self {
         Self {
             id: next_shape_id(),
-            transform,
+            transform: CachedTransform::new(transform),
+            parent_transform: CachedTransform::default(),
             material,
         }
     }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
 }
 
 impl Shape for Plane {
     fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
-        if ray.direction.y.abs() < EPSILON {
+        let transformed_ray = ray.transform(*self.transform.inverse());
+
+        if transformed_ray.direction.y.abs() < EPSILON {
             vec![]
         } else {
-            let t = -ray.origin.y / ray.direction.y;
+            let t = -transformed_ray.origin.y / transformed_ray.direction.y;
             vec![Intersection::new(t, self)]
         }
     }
 
     fn normal_at(&self, _world_point: Tuple) -> Tuple {
-        vector(0.0, 1.0, 0.0)
+        self.normal_to_world(vector(0.0, 1.0, 0.0))
     }
 
     fn material(&self) -> &Material {
         &self.material
     }
 
-    fn transform(&self) -> &Matrix4 {
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            point(-RtcFl::INFINITY, 0.0, -RtcFl::INFINITY),
+            point(RtcFl::INFINITY, 0.0, RtcFl::INFINITY),
+        )
+    }
+
+    fn transform(&self) -> &CachedTransform {
         &self.transform
     }
 
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+    }
+
     fn id(&self) -> usize {
         self.id
     }
@@ -75,9 +106,12 @@ impl Shape for Plane {
 #[cfg(test)]
 mod tests {
     use crate::{
+        material::Material,
         ray::Ray,
         shape::Shape,
+        transformation::{rotation_z, translation},
         tuples::{point, vector},
+        util::{RtcFl, PI},
     };
 
     use super::Plane;
@@ -116,7 +150,7 @@ mod tests {
         let p = Plane::default();
         let r = Ray::new(&point(0.0, 1.0, 0.0), &vector(0.0, -1.0, 0.0));
         let xs = p.intersect(&r);
-        assert!(xs.iter().count() == 1);
+        assert!(xs.len() == 1);
         assert!(xs[0].t == 1.0);
         assert!(xs[0].shape.id() == p.id());
     }
@@ -126,8 +160,38 @@ mod tests {
         let p = Plane::default();
         let r = Ray::new(&point(0.0, -1.0, 0.0), &vector(0.0, 1.0, 0.0));
         let xs = p.intersect(&r);
-        assert!(xs.iter().count() == 1);
+        assert!(xs.len() == 1);
         assert!(xs[0].t == 1.0);
         assert!(xs[0].shape.id() == p.id());
     }
+
+    #[test]
+    fn intersecting_a_translated_plane() {
+        let p = Plane::new(translation(0.0, -1.0, 0.0), Material::default());
+        let r = Ray::new(&point(0.0, 1.0, 0.0), &vector(0.0, -1.0, 0.0));
+        let xs = p.intersect(&r);
+        assert!(xs.len() == 1);
+        assert!(xs[0].t == 2.0);
+    }
+
+    #[test]
+    fn the_normal_of_a_rotated_plane() {
+        let p = Plane::new(rotation_z(PI / 2.0), Material::default());
+        let n = p.normal_at(point(0.0, 5.0, 0.0));
+        assert!(n == vector(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn a_plane_has_a_bounding_box() {
+        let p = Plane::default();
+
+        let b = p.bounds();
+
+        assert_eq!(b.min.x, -RtcFl::INFINITY);
+        assert_eq!(b.min.y, 0.0);
+        assert_eq!(b.min.z, -RtcFl::INFINITY);
+        assert_eq!(b.max.x, RtcFl::INFINITY);
+        assert_eq!(b.max.y, 0.0);
+        assert_eq!(b.max.z, RtcFl::INFINITY);
+    }
 }
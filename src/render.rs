@@ -8,21 +8,39 @@ use indicatif::ProgressBar;
 use rand::seq::SliceRandom;
 
 use crate::{
-    camera::{ray_for_pixel, Camera},
+    antialias::combine_samples,
+    camera::{ray_for_subpixel, Camera},
     canvas::Canvas,
+    color::Color,
+    util::RtcFl,
     world::{color_at, World},
 };
 
 const TILE_SIZE: usize = 16;
 
-pub fn render(camera: &Camera, world: World, bar: &ProgressBar) -> Canvas {
-    let mut canvas = Canvas::new(camera.hsize, camera.vsize);
+/// The color of pixel (`px`, `py`), from as many rays as the camera's anti-aliasing asks for.
+pub fn pixel_color(camera: &Camera, world: &World, px: usize, py: usize) -> Color {
+    let samples = camera
+        .antialiasing
+        .weighted_offsets()
+        .into_iter()
+        .map(|(dx, dy, weight)| {
+            let ray = ray_for_subpixel(camera, px as RtcFl + dx, py as RtcFl + dy);
+            (dx, dy, weight, color_at(world, ray))
+        })
+        .collect::<Vec<_>>();
+
+    combine_samples(&samples)
+}
+
+pub fn render(camera: &Camera, mut world: World, bar: &ProgressBar) -> Canvas {
+    world.ensure_bvh();
+    let mut canvas = Canvas::with_tone_mapping(camera.hsize, camera.vsize, camera.tone_mapping);
 
     for y in 0..camera.vsize - 1 {
         for x in 0..camera.hsize - 1 {
             bar.inc(1);
-            let ray = ray_for_pixel(camera, x, y);
-            let color = color_at(&world, ray);
+            let color = pixel_color(camera, &world, x, y);
             canvas.write_pixel(x, y, color);
         }
     }
@@ -34,6 +52,10 @@ pub fn render_parallel(camera: &Camera, world: &World, bar: &ProgressBar, single
     // Initialize parallelism
     let (tx, rx) = mpsc::channel();
 
+    // Build the BVH once here rather than in every thread's copy
+    let mut world = world.clone();
+    world.ensure_bvh();
+
     let num_threads: usize = if single {
         1
     } else {
@@ -57,7 +79,7 @@ pub fn render_parallel(camera: &Camera, world: &World, bar: &ProgressBar, single
     }
 
     if tiles.is_empty() {
-        return Canvas::new(camera.hsize, camera.vsize);
+        return Canvas::with_tone_mapping(camera.hsize, camera.vsize, camera.tone_mapping);
     }
 
     let tiles = Arc::new(tiles);
@@ -82,8 +104,7 @@ pub fn render_parallel(camera: &Camera, world: &World, bar: &ProgressBar, single
                 for y in *ty0..y_end {
                     for x in *tx0..x_end {
                         bar.inc(1);
-                        let ray = ray_for_pixel(&camera, x, y);
-                        let color = color_at(&world, ray);
+                        let color = pixel_color(&camera, &world, x, y);
                         tx.send((x, y, color)).expect("Failed to send pixel data.");
                     }
                 }
@@ -95,7 +116,7 @@ pub fn render_parallel(camera: &Camera, world: &World, bar: &ProgressBar, single
     drop(tx);
 
     // Init canvas
-    let mut canvas = Canvas::new(camera.hsize, camera.vsize);
+    let mut canvas = Canvas::with_tone_mapping(camera.hsize, camera.vsize, camera.tone_mapping);
 
     for (x, y, color) in rx {
         canvas.write_pixel(x, y, color);
@@ -115,6 +136,10 @@ where
 {
     let (tx, rx) = mpsc::channel();
 
+    // Build the BVH once here rather than in every thread's copy
+    let mut world = world.clone();
+    world.ensure_bvh();
+
     let num_threads: usize = available_parallelism().map(|n| n.get()).unwrap_or(1);
 
     println!("Number of threads: {}", num_threads);
@@ -134,7 +159,7 @@ where
     }
 
     if tiles.is_empty() {
-        return Canvas::new(camera.hsize, camera.vsize);
+        return Canvas::with_tone_mapping(camera.hsize, camera.vsize, camera.tone_mapping);
     }
 
     // Set to true to randomize pixels for effect
@@ -164,8 +189,7 @@ where
 
                 for y in *ty0..y_end {
                     for x in *tx0..x_end {
-                        let ray = ray_for_pixel(&camera, x, y);
-                        let color = color_at(&world, ray);
+                        let color = pixel_color(&camera, &world, x, y);
                         tx.send((x, y, color)).expect("Failed to send pixel data.");
                     }
                 }
@@ -175,7 +199,7 @@ where
 
     drop(tx);
 
-    let mut canvas = Canvas::new(camera.hsize, camera.vsize);
+    let mut canvas = Canvas::with_tone_mapping(camera.hsize, camera.vsize, camera.tone_mapping);
     let mut last_update = Instant::now();
 
     for (x, y, color) in rx {
@@ -1,7 +1,9 @@
 use crate::{
+    bounds::BoundingBox,
+    bvh::BvhSplit,
     intersections::Intersection,
     material::Material,
-    matrix::Matrix4,
+    matrix::{CachedTransform, Matrix4},
     ray::Ray,
     tuples::Tuple,
 };
@@ -14,12 +16,52 @@ pub fn next_shape_id() -> usize {
 }
 
 pub trait Shape: Send + Sync + std::fmt::Debug {
+    /// Intersects a ray given in the space of the shape's parent, which is world space
+    /// unless the shape sits in a group.
     fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>>;
     fn normal_at(&self, world_point: Tuple) -> Tuple;
+    /// Normal at a point we know came from `hit`. Shapes that need more than the point,
+    /// like smooth triangles interpolating their vertex normals, override this.
+    fn normal_at_hit(&self, world_point: Tuple, _hit: &Intersection) -> Tuple {
+        self.normal_at(world_point)
+    }
     fn material(&self) -> &Material;
-    fn transform(&self) -> &Matrix4;
+    /// Extent of the shape in its own object space.
+    fn bounds(&self) -> BoundingBox;
+    fn transform(&self) -> &CachedTransform;
+    /// The combined transform of every group above this shape, identity if it has no parent.
+    fn parent_transform(&self) -> &CachedTransform;
+    fn set_parent_transform(&mut self, transform: Matrix4);
     fn id(&self) -> usize;
     fn clone_boxed(&self) -> Box<dyn Shape>;
+
+    /// Is `shape` either this shape or somewhere below it? Compared by address rather than id,
+    /// because clones share their original's id.
+    fn includes(&self, shape: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, shape)
+    }
+
+    /// Extent of the shape once its own transform is applied, i.e. in the space of its parent.
+    fn parent_space_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.transform().matrix())
+    }
+
+    /// Builds a BVH over any shapes this one contains. Does nothing for primitives.
+    fn build_bvh(&mut self, _split: BvhSplit) {}
+
+    fn world_to_object(&self, world_point: Tuple) -> Tuple {
+        *self.transform().inverse() * (*self.parent_transform().inverse() * world_point)
+    }
+
+    fn normal_to_world(&self, object_normal: Tuple) -> Tuple {
+        let mut world_normal = *self.parent_transform().inverse_transpose()
+            * (*self.transform().inverse_transpose() * object_normal);
+
+        // Hack to reset the w component, avoiding some more complex matrix math
+        world_normal.w = 0.0;
+
+        world_normal.normalize()
+    }
 }
 
 #[cfg(test)]
@@ -31,6 +73,6 @@ mod tests {
     fn the_default_transformation() {
         let s = Sphere::default();
 
-        assert_eq!(*s.transform(), Matrix4::identity());
+        assert_eq!(*s.transform().matrix(), Matrix4::identity());
     }
 }
@@ -1,7 +1,8 @@
 use crate::{
+    bounds::BoundingBox,
     intersections::Intersection,
     material::Material,
-    matrix::Matrix4,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
     ray::Ray,
     shape::{next_shape_id, Shape},
     tuples::{point, Tuple},
@@ -10,7 +11,8 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct Sphere {
     pub id: usize,
-    pub transform: Matrix4,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
     pub material: Material,
 }
 
@@ -18,7 +20,8 @@ impl Default for Sphere {
     fn default() -> Self {
         Self {
             id: next_shape_id(),
-            transform: Matrix4::identity(),
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
             material: Material::default(),
         }
     }
@@ -34,19 +37,39 @@ impl Sphere {
     pub fn new(transform: Matrix4, material: Material) -> Self {
         Self {
             id: next_shape_id(),
-            transform,
+            transform: CachedTransform::new(transform),
+            parent_transform: CachedTransform::default(),
             material,
         }
     }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
+}
+
+/// A fully transparent sphere with the refractive index of glass.
+#[cfg(test)]
+pub(crate) fn glass_sphere_for_test() -> Sphere {
+    Sphere::new(
+        Matrix4::identity(),
+        Material {
+            transparency: 1.0,
+            refractive_index: 1.5,
+            ..Default::default()
+        },
+    )
 }
 
 impl Shape for Sphere {
     fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
-        let transformed_ray = ray.transform(
-            self.transform
-                .try_inverse()
-                .expect("Cannot invert this transform."),
-        );
+        let transformed_ray = ray.transform(*self.transform.inverse());
 
         let sphere_to_ray = transformed_ray.origin - point(0.0, 0.0, 0.0);
         let a = transformed_ray.direction.dot(transformed_ray.direction);
@@ -66,38 +89,32 @@ impl Shape for Sphere {
     }
 
     fn normal_at(&self, world_point: Tuple) -> Tuple {
-        let inverse_transform = match self.transform.try_inverse() {
-            Some(matrix) => matrix,
-            None => {
-                println!("{}", self.transform);
-                panic!("Cannot invert matrix.");
-            }
-        };
-
-        //println!("Inverse transform: {:?}", inverse_transform);
-
-        let object_point = inverse_transform * world_point;
-        //println!("Object point: {:?}", object_point);
-        let object_normal = (object_point - point(0.0, 0.0, 0.0)).normalize();
-        //println!("Object normal: {:?}", object_normal);
+        let object_point = self.world_to_object(world_point);
+        let object_normal = object_point - point(0.0, 0.0, 0.0);
 
-        let mut world_normal = inverse_transform.transpose() * object_normal;
-        //println!("World normal before normalization: {:?}", world_normal);
-
-        // Hack to reset the w component, avoiding some more complex matrix math
-        world_normal.w = 0.0;
-
-        world_normal.normalize()
+        self.normal_to_world(object_normal)
     }
 
     fn material(&self) -> &Material {
         &self.material
     }
 
-    fn transform(&self) -> &Matrix4 {
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1.0, -1.0, -1.0), point(1.0, 1.0, 1.0))
+    }
+
+    fn transform(&self) -> &CachedTransform {
         &self.transform
     }
 
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+    }
+
     fn id(&self) -> usize {
         self.id
     }
@@ -113,11 +130,11 @@ mod tests {
 
     use crate::{
         material::Material,
-        matrix::Matrix4,
+        matrix::{Matrix4, NotInvertible},
         ray::Ray,
         shape::Shape,
-        sphere::Sphere,
-        transformation::{rotation_z, scaling, Transformation},
+        sphere::{glass_sphere_for_test, Sphere},
+        transformation::{rotation_z, scaling, translation, Transformation},
         tuples::{point, vector},
         util::{RtcFl, PI},
     };
@@ -143,7 +160,7 @@ mod tests {
         let s = Sphere::default();
         let m = Matrix4::identity();
 
-        assert_eq!(s.transform, m);
+        assert_eq!(*s.transform.matrix(), m);
     }
 
     #[test]
@@ -151,18 +168,26 @@ mod tests {
         let mut s = Sphere::default();
         let t = Transformation::new().translation(2.0, 3.0, 4.0);
 
-        s.transform = t.get();
+        s.set_transform(t.get());
+
+        assert_eq!(*s.transform.matrix(), t.get());
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot invert this transform.")]
+    fn a_non_invertible_transform_is_rejected_when_set() {
+        let mut s = Sphere::default();
 
-        assert_eq!(s.transform, t.get());
+        s.set_transform(scaling(1.0, 0.0, 1.0));
     }
 
     #[test]
     fn intersect_scaled_sphere_with_ray() {
         let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
-        let s = Sphere {
-            transform: Transformation::new().scaling(2.0, 2.0, 2.0).get(),
-            ..Default::default()
-        };
+        let s = Sphere::new(
+            Transformation::new().scaling(2.0, 2.0, 2.0).get(),
+            Material::default(),
+        );
 
         let xs = s.intersect(&r);
 
@@ -174,10 +199,10 @@ mod tests {
     #[test]
     fn intersect_translated_sphere_with_ray() {
         let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
-        let s = Sphere {
-            transform: Transformation::new().translation(5.0, 0.0, 0.0).get(),
-            ..Default::default()
-        };
+        let s = Sphere::new(
+            Transformation::new().translation(5.0, 0.0, 0.0).get(),
+            Material::default(),
+        );
 
         let xs = s.intersect(&r);
 
@@ -228,10 +253,10 @@ mod tests {
 
     #[test]
     fn computing_normal_on_translated_sphere() {
-        let s = Sphere {
-            transform: Transformation::new().translation(0.0, 1.0, 0.0).get(),
-            ..Default::default()
-        };
+        let s = Sphere::new(
+            Transformation::new().translation(0.0, 1.0, 0.0).get(),
+            Material::default(),
+        );
 
         let n = s.normal_at(point(0.0, 1.70711, -FRAC_1_SQRT_2));
 
@@ -240,10 +265,10 @@ mod tests {
 
     #[test]
     fn computing_normal_on_transformed_sphere() {
-        let s = Sphere {
-            transform: scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0),
-            ..Default::default()
-        };
+        let s = Sphere::new(
+            scaling(1.0, 0.5, 1.0) * rotation_z(PI / 5.0),
+            Material::default(),
+        );
         let sqrt2over2 = (2.0 as RtcFl).sqrt() / 2.0;
 
         let n = s.normal_at(point(0.0, sqrt2over2, -sqrt2over2));
@@ -272,4 +297,49 @@ mod tests {
 
         assert_eq!(s.material.shininess, 900.0);
     }
+
+    #[test]
+    fn a_sphere_has_a_bounding_box() {
+        let s = Sphere::default();
+
+        let b = s.bounds();
+
+        assert_eq!(b.min, point(-1.0, -1.0, -1.0));
+        assert_eq!(b.max, point(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn querying_a_shapes_bounding_box_in_its_parents_space() {
+        let s = Sphere::new(
+            translation(1.0, -3.0, 5.0) * scaling(0.5, 2.0, 4.0),
+            Material::default(),
+        );
+
+        let b = s.parent_space_bounds();
+
+        assert_eq!(b.min, point(0.5, -5.0, 1.0));
+        assert_eq!(b.max, point(1.5, -1.0, 9.0));
+    }
+
+    #[test]
+    fn a_helper_for_producing_a_sphere_with_a_glassy_material() {
+        let s = glass_sphere_for_test();
+
+        assert_eq!(*s.transform().matrix(), Matrix4::identity());
+        assert_eq!(s.material.transparency, 1.0);
+        assert_eq!(s.material.refractive_index, 1.5);
+    }
+
+    #[test]
+    fn a_transform_that_cannot_be_inverted_is_rejected() {
+        let mut s = Sphere::default();
+        s.set_transform(translation(1.0, 2.0, 3.0));
+
+        assert_eq!(
+            s.try_set_transform(scaling(1.0, 0.0, 1.0)),
+            Err(NotInvertible)
+        );
+        assert_eq!(*s.transform().matrix(), translation(1.0, 2.0, 3.0));
+        assert_eq!(s.try_set_transform(scaling(2.0, 2.0, 2.0)), Ok(()));
+    }
 }
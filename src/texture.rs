@@ -0,0 +1,377 @@
+use std::{path::Path, sync::Arc};
+
+use crate::{
+    canvas::{Canvas, CanvasError},
+    color::Color,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
+    tuples::Tuple,
+    util::{RtcFl, PI},
+};
+
+/// How a point on (or in) an object is flattened into texture coordinates, both in 0..1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UvMapping {
+    /// Tiles the xz plane with one copy of the texture per unit square.
+    Planar,
+    /// Wraps the texture once around a unit sphere, u around the y axis and v from pole to pole.
+    Spherical,
+    /// Wraps the texture around the y axis, repeating every unit of height.
+    Cylindrical,
+    /// Puts a whole copy of the texture on each face of a unit cube.
+    Cube,
+}
+
+impl UvMapping {
+    pub fn uv(&self, point: Tuple) -> (RtcFl, RtcFl) {
+        match self {
+            UvMapping::Planar => (point.x.rem_euclid(1.0), point.z.rem_euclid(1.0)),
+            UvMapping::Spherical => {
+                let radius = (point.x.powi(2) + point.y.powi(2) + point.z.powi(2)).sqrt();
+                let phi = (point.y / radius).acos();
+
+                (azimuth(point), 1.0 - phi / PI)
+            }
+            UvMapping::Cylindrical => (azimuth(point), point.y.rem_euclid(1.0)),
+            UvMapping::Cube => cube_uv(point),
+        }
+    }
+}
+
+/// Angle around the y axis as a fraction of a turn, increasing counter-clockwise seen from above.
+fn azimuth(point: Tuple) -> RtcFl {
+    let theta = point.x.atan2(point.z);
+
+    1.0 - (theta / (2.0 * PI) + 0.5)
+}
+
+fn cube_uv(point: Tuple) -> (RtcFl, RtcFl) {
+    let (x, y, z) = (point.x, point.y, point.z);
+    let wrap = |a: RtcFl| a.rem_euclid(2.0) / 2.0;
+    let coord = x.abs().max(y.abs()).max(z.abs());
+
+    if coord == x {
+        (wrap(1.0 - z), wrap(y + 1.0))
+    } else if coord == -x {
+        (wrap(z + 1.0), wrap(y + 1.0))
+    } else if coord == y {
+        (wrap(x + 1.0), wrap(1.0 - z))
+    } else if coord == -y {
+        (wrap(x + 1.0), wrap(z + 1.0))
+    } else if coord == z {
+        (wrap(x + 1.0), wrap(y + 1.0))
+    } else {
+        (wrap(1.0 - x), wrap(y + 1.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Sampling {
+    /// The single closest pixel.
+    #[default]
+    Nearest,
+    /// A weighted average of the four surrounding pixels.
+    Bilinear,
+}
+
+/// A picture wrapped onto an object through a `UvMapping`.
+///
+/// The image is shared, so cloning the pattern (which happens whenever its shape is cloned)
+/// doesn't copy the pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePattern {
+    pub canvas: Arc<Canvas>,
+    pub mapping: UvMapping,
+    pub sampling: Sampling,
+    transform: CachedTransform,
+}
+
+impl ImagePattern {
+    pub fn new(canvas: impl Into<Arc<Canvas>>, mapping: UvMapping) -> Self {
+        Self {
+            canvas: canvas.into(),
+            mapping,
+            sampling: Sampling::default(),
+            transform: CachedTransform::default(),
+        }
+    }
+
+    /// Loads a `.png`, `.ppm` or `.hdr` file as the texture. PNG and PPM files are taken to be
+    /// sRGB encoded and are linearized, so they don't get encoded a second time on output.
+    pub fn from_file(path: impl AsRef<Path>, mapping: UvMapping) -> Result<Self, CanvasError> {
+        let path = path.as_ref();
+        let mut canvas = Canvas::from_file(path)?;
+        let is_hdr = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("hdr"));
+        if !is_hdr {
+            canvas.linearize();
+        }
+
+        Ok(Self::new(canvas, mapping))
+    }
+
+    pub fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
+
+    pub(crate) fn pattern_at(&self, point: Tuple) -> Color {
+        let (u, v) = self.mapping.uv(point);
+
+        self.uv_color(u, v)
+    }
+
+    /// The color at texture coordinates `u` and `v`, with v = 0 at the bottom of the image.
+    /// An empty image is black everywhere.
+    pub fn uv_color(&self, u: RtcFl, v: RtcFl) -> Color {
+        if self.canvas.width == 0 || self.canvas.height == 0 {
+            return Color::black();
+        }
+
+        let x = u.clamp(0.0, 1.0) * (self.canvas.width - 1) as RtcFl;
+        let y = (1.0 - v.clamp(0.0, 1.0)) * (self.canvas.height - 1) as RtcFl;
+
+        match self.sampling {
+            Sampling::Nearest => *self.canvas.pixel_at(x.round() as usize, y.round() as usize),
+            Sampling::Bilinear => {
+                let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+                let x1 = (x0 + 1).min(self.canvas.width - 1);
+                let y1 = (y0 + 1).min(self.canvas.height - 1);
+                let (fx, fy) = (x - x0 as RtcFl, y - y0 as RtcFl);
+
+                let top = lerp(
+                    *self.canvas.pixel_at(x0, y0),
+                    *self.canvas.pixel_at(x1, y0),
+                    fx,
+                );
+                let bottom = lerp(
+                    *self.canvas.pixel_at(x0, y1),
+                    *self.canvas.pixel_at(x1, y1),
+                    fx,
+                );
+
+                lerp(top, bottom, fy)
+            }
+        }
+    }
+}
+
+fn lerp(a: Color, b: Color, t: RtcFl) -> Color {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        canvas::{Canvas, PngOptions},
+        color::Color,
+        pattern::Pattern,
+        sphere::Sphere,
+        texture::{ImagePattern, Sampling, UvMapping},
+        tonemap::ToneMapping,
+        tuples::{point, Tuple},
+        util::{equal, RtcFl},
+    };
+
+    fn assert_uv(mapping: UvMapping, p: Tuple, u: RtcFl, v: RtcFl) {
+        let (actual_u, actual_v) = mapping.uv(p);
+
+        assert!(equal(actual_u, u), "u of {:?} was {}", p, actual_u);
+        assert!(equal(actual_v, v), "v of {:?} was {}", p, actual_v);
+    }
+
+    #[test]
+    fn spherical_mapping_on_a_3d_point() {
+        let half_sqrt2 = (2.0 as RtcFl).sqrt() / 2.0;
+        let examples = [
+            (point(0.0, 0.0, -1.0), 0.0, 0.5),
+            (point(1.0, 0.0, 0.0), 0.25, 0.5),
+            (point(0.0, 0.0, 1.0), 0.5, 0.5),
+            (point(-1.0, 0.0, 0.0), 0.75, 0.5),
+            (point(0.0, 1.0, 0.0), 0.5, 1.0),
+            (point(0.0, -1.0, 0.0), 0.5, 0.0),
+            (point(half_sqrt2, half_sqrt2, 0.0), 0.25, 0.75),
+        ];
+
+        for (p, u, v) in examples {
+            assert_uv(UvMapping::Spherical, p, u, v);
+        }
+    }
+
+    #[test]
+    fn planar_mapping_on_a_3d_point() {
+        let examples = [
+            (point(0.25, 0.0, 0.5), 0.25, 0.5),
+            (point(0.25, 0.0, -0.25), 0.25, 0.75),
+            (point(0.25, 0.5, -0.25), 0.25, 0.75),
+            (point(1.25, 0.0, 0.5), 0.25, 0.5),
+            (point(0.25, 0.0, -1.75), 0.25, 0.25),
+            (point(1.0, 0.0, -1.0), 0.0, 0.0),
+            (point(0.0, 0.0, 0.0), 0.0, 0.0),
+        ];
+
+        for (p, u, v) in examples {
+            assert_uv(UvMapping::Planar, p, u, v);
+        }
+    }
+
+    #[test]
+    fn cylindrical_mapping_on_a_3d_point() {
+        let half_sqrt2 = (2.0 as RtcFl).sqrt() / 2.0;
+        let examples = [
+            (point(0.0, 0.0, -1.0), 0.0, 0.0),
+            (point(0.0, 0.5, -1.0), 0.0, 0.5),
+            (point(0.0, 1.0, -1.0), 0.0, 0.0),
+            (point(half_sqrt2, 0.5, -half_sqrt2), 0.125, 0.5),
+            (point(1.0, 0.5, 0.0), 0.25, 0.5),
+            (point(half_sqrt2, 0.5, half_sqrt2), 0.375, 0.5),
+            (point(0.0, -0.25, 1.0), 0.5, 0.75),
+            (point(-half_sqrt2, 0.5, half_sqrt2), 0.625, 0.5),
+            (point(-1.0, 1.25, 0.0), 0.75, 0.25),
+            (point(-half_sqrt2, 0.5, -half_sqrt2), 0.875, 0.5),
+        ];
+
+        for (p, u, v) in examples {
+            assert_uv(UvMapping::Cylindrical, p, u, v);
+        }
+    }
+
+    #[test]
+    fn cube_mapping_on_each_face() {
+        let examples = [
+            // Front
+            (point(-0.5, 0.5, 1.0), 0.25, 0.75),
+            (point(0.5, -0.5, 1.0), 0.75, 0.25),
+            // Back
+            (point(0.5, 0.5, -1.0), 0.25, 0.75),
+            (point(-0.5, -0.5, -1.0), 0.75, 0.25),
+            // Left
+            (point(-1.0, 0.5, -0.5), 0.25, 0.75),
+            (point(-1.0, -0.5, 0.5), 0.75, 0.25),
+            // Right
+            (point(1.0, 0.5, 0.5), 0.25, 0.75),
+            (point(1.0, -0.5, -0.5), 0.75, 0.25),
+            // Up
+            (point(-0.5, 1.0, -0.5), 0.25, 0.75),
+            (point(0.5, 1.0, 0.5), 0.75, 0.25),
+            // Down
+            (point(-0.5, -1.0, 0.5), 0.25, 0.75),
+            (point(0.5, -1.0, -0.5), 0.75, 0.25),
+        ];
+
+        for (p, u, v) in examples {
+            assert_uv(UvMapping::Cube, p, u, v);
+        }
+    }
+
+    // A 10x10 canvas where each pixel's grey level encodes its position
+    fn gradient_canvas_for_test() -> Canvas {
+        let mut canvas = Canvas::new(10, 10);
+        for y in 0..10 {
+            for x in 0..10 {
+                let level = (x + y * 10) as RtcFl / 100.0;
+                canvas.write_pixel(x, y, Color::new(level, level, level));
+            }
+        }
+        canvas
+    }
+
+    #[test]
+    fn nearest_sampling_picks_the_closest_pixel() {
+        let pattern = ImagePattern::new(gradient_canvas_for_test(), UvMapping::Planar);
+        let examples = [
+            (0.0, 0.0, 0.9),
+            (0.3, 0.0, 0.93),
+            (0.6, 0.3, 0.65),
+            (1.0, 1.0, 0.09),
+        ];
+
+        for (u, v, expected) in examples {
+            let level = pattern.uv_color(u, v).red;
+            assert!(equal(level, expected), "({}, {}) was {}", u, v, level);
+        }
+    }
+
+    #[test]
+    fn bilinear_sampling_blends_neighbouring_pixels() {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(1, 0, Color::white());
+        canvas.write_pixel(1, 1, Color::white());
+        let mut pattern = ImagePattern::new(canvas, UvMapping::Planar);
+        pattern.sampling = Sampling::Bilinear;
+
+        assert_eq!(pattern.uv_color(0.0, 0.5), Color::black());
+        assert_eq!(pattern.uv_color(0.25, 0.5), Color::new(0.25, 0.25, 0.25));
+        assert_eq!(pattern.uv_color(0.5, 0.0), Color::new(0.5, 0.5, 0.5));
+        assert_eq!(pattern.uv_color(1.0, 1.0), Color::white());
+    }
+
+    #[test]
+    fn an_empty_image_is_black() {
+        for (width, height) in [(0, 0), (0, 4), (4, 0)] {
+            let mut pattern = ImagePattern::new(Canvas::new(width, height), UvMapping::Planar);
+
+            assert_eq!(pattern.uv_color(0.5, 0.5), Color::black());
+            pattern.sampling = Sampling::Bilinear;
+            assert_eq!(pattern.uv_color(0.5, 0.5), Color::black());
+        }
+    }
+
+    #[test]
+    fn cloning_an_image_pattern_shares_the_image() {
+        let pattern = Pattern::Image(ImagePattern::new(Canvas::new(64, 64), UvMapping::Planar));
+        let copy = pattern.clone();
+
+        match (&pattern, &copy) {
+            (Pattern::Image(a), Pattern::Image(b)) => {
+                assert!(Arc::ptr_eq(&a.canvas, &b.canvas));
+                assert_eq!(Arc::strong_count(&a.canvas), 2);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn textures_loaded_from_png_are_linear() {
+        let path = std::env::temp_dir().join("rtc-rs-mid-grey-texture.png");
+        let mut canvas = Canvas::with_tone_mapping(1, 1, ToneMapping::display(Default::default()));
+        canvas.write_pixel(0, 0, Color::new(0.2, 0.2, 0.2));
+        canvas.save_png(&path, &PngOptions::default()).unwrap();
+
+        let pattern = ImagePattern::from_file(&path, UvMapping::Planar).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // 0.2 is stored as sRGB 124, which decodes back to about 0.2
+        let texel = pattern.uv_color(0.5, 0.5);
+        assert!((texel.red - 0.2).abs() < 0.005, "got {:?}", texel);
+        assert_eq!(texel.red, texel.green);
+    }
+
+    #[test]
+    fn image_pattern_on_an_object() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.write_pixel(1, 0, Color::white());
+        let pattern = Pattern::Image(ImagePattern::new(canvas, UvMapping::Planar));
+        let object = Sphere::default();
+
+        assert_eq!(
+            pattern.pattern_at_object(&object, point(0.1, 0.0, 0.5)),
+            Color::black()
+        );
+        assert_eq!(
+            pattern.pattern_at_object(&object, point(0.9, 0.0, 0.5)),
+            Color::white()
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{color::Color, util::RtcFl};
+
+/// How linear light above 1.0 is squeezed into the displayable 0..1 range.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ToneMapOperator {
+    /// Cut everything off at 1.0.
+    #[default]
+    Clamp,
+    /// `c / (1 + c)`, which never quite reaches white.
+    Reinhard,
+    /// Reinhard, rescaled so that `white` and anything brighter maps to 1.0.
+    ExtendedReinhard { white: RtcFl },
+    /// Krzysztof Narkowicz's fit of the ACES filmic curve.
+    AcesFilmic,
+}
+
+/// Turns the linear colors a render produces into values for an 8 or 16-bit image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapping {
+    pub operator: ToneMapOperator,
+    /// Brightness adjustment in stops, applied before the operator. +1 doubles the light.
+    pub exposure: RtcFl,
+    /// Encode with the sRGB transfer function instead of writing linear values.
+    pub srgb: bool,
+}
+
+impl Default for ToneMapping {
+    /// Linear values, clamped. This is what the book's tests expect.
+    fn default() -> Self {
+        Self {
+            operator: ToneMapOperator::Clamp,
+            exposure: 0.0,
+            srgb: false,
+        }
+    }
+}
+
+impl ToneMapping {
+    /// `operator` followed by sRGB encoding, which is what image viewers expect.
+    pub fn display(operator: ToneMapOperator) -> Self {
+        Self {
+            operator,
+            exposure: 0.0,
+            srgb: true,
+        }
+    }
+
+    /// Each component of `color` mapped into 0..1.
+    pub fn map(&self, color: Color) -> Color {
+        let scale = (2.0 as RtcFl).powf(self.exposure);
+        let map = |c: RtcFl| {
+            let c = self.map_component(c.max(0.0) * scale).clamp(0.0, 1.0);
+            if self.srgb {
+                srgb_encode(c)
+            } else {
+                c
+            }
+        };
+
+        Color::new(map(color.red), map(color.green), map(color.blue))
+    }
+
+    pub fn to_u8(&self, color: Color) -> [u8; 3] {
+        let c = self.map(color);
+
+        [c.red, c.green, c.blue].map(|v| (v * 255.0).round() as u8)
+    }
+
+    pub fn to_u16(&self, color: Color) -> [u16; 3] {
+        let c = self.map(color);
+
+        [c.red, c.green, c.blue].map(|v| (v * 65535.0).round() as u16)
+    }
+
+    fn map_component(&self, c: RtcFl) -> RtcFl {
+        match self.operator {
+            ToneMapOperator::Clamp => c,
+            ToneMapOperator::Reinhard => c / (1.0 + c),
+            ToneMapOperator::ExtendedReinhard { white } => {
+                c * (1.0 + c / white.powi(2)) / (1.0 + c)
+            }
+            ToneMapOperator::AcesFilmic => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+        }
+    }
+}
+
+/// The sRGB transfer function, from linear light to encoded 0..1.
+pub fn srgb_encode(linear: RtcFl) -> RtcFl {
+    if linear <= 0.0031308 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// The inverse of `srgb_encode`.
+pub fn srgb_decode(encoded: RtcFl) -> RtcFl {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Color,
+        tonemap::{srgb_decode, srgb_encode, ToneMapOperator, ToneMapping},
+        util::equal,
+    };
+
+    fn mapping(operator: ToneMapOperator) -> ToneMapping {
+        ToneMapping {
+            operator,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn the_default_mapping_clamps_linear_values() {
+        let tm = ToneMapping::default();
+
+        assert_eq!(
+            tm.map(Color::new(-0.5, 0.5, 1.5)),
+            Color::new(0.0, 0.5, 1.0)
+        );
+        assert_eq!(tm.to_u8(Color::new(0.0, 0.5, 1.0)), [0, 128, 255]);
+        assert_eq!(tm.to_u16(Color::new(0.0, 0.5, 1.0)), [0, 32768, 65535]);
+    }
+
+    #[test]
+    fn reinhard_compresses_highlights() {
+        let tm = mapping(ToneMapOperator::Reinhard);
+
+        assert_eq!(
+            tm.map(Color::new(1.0, 3.0, 0.0)),
+            Color::new(0.5, 0.75, 0.0)
+        );
+    }
+
+    #[test]
+    fn extended_reinhard_maps_the_white_point_to_one() {
+        let tm = mapping(ToneMapOperator::ExtendedReinhard { white: 4.0 });
+
+        assert_eq!(tm.map(Color::new(4.0, 10.0, 0.0)).red, 1.0);
+        assert_eq!(tm.map(Color::new(4.0, 10.0, 0.0)).green, 1.0);
+        assert!(equal(tm.map(Color::new(1.0, 0.0, 0.0)).red, 0.53125));
+    }
+
+    #[test]
+    fn aces_filmic_runs_from_black_to_white() {
+        let tm = mapping(ToneMapOperator::AcesFilmic);
+
+        assert_eq!(tm.map(Color::black()), Color::black());
+        assert!(equal(tm.map(Color::new(0.18, 0.0, 0.0)).red, 0.26698));
+        assert_eq!(tm.map(Color::new(100.0, 0.0, 0.0)).red, 1.0);
+    }
+
+    #[test]
+    fn exposure_is_measured_in_stops() {
+        let tm = ToneMapping {
+            exposure: 1.0,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            tm.map(Color::new(0.25, 0.1, 0.6)),
+            Color::new(0.5, 0.2, 1.0)
+        );
+    }
+
+    #[test]
+    fn srgb_encoding_brightens_midtones() {
+        let tm = ToneMapping::display(ToneMapOperator::Clamp);
+
+        assert!(equal(srgb_encode(0.5), 0.73536));
+        assert!(equal(srgb_encode(0.002), 0.02584));
+        assert_eq!(tm.to_u8(Color::new(0.5, 0.0, 1.0)), [188, 0, 255]);
+    }
+
+    #[test]
+    fn srgb_decoding_undoes_encoding() {
+        for v in [0.0, 0.001, 0.04, 0.2, 0.5, 0.9, 1.0] {
+            assert!(equal(srgb_decode(srgb_encode(v)), v));
+        }
+    }
+}
@@ -0,0 +1,430 @@
+use crate::{
+    bounds::BoundingBox,
+    intersections::Intersection,
+    material::Material,
+    matrix::{CachedTransform, Matrix4, NotInvertible},
+    ray::Ray,
+    shape::{next_shape_id, Shape},
+    tuples::Tuple,
+    util::{RtcFl, EPSILON},
+};
+
+/// A flat triangle. Edges and normal are precomputed since they never change.
+#[derive(Debug, Clone)]
+pub struct Triangle {
+    pub id: usize,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
+    pub material: Material,
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+    pub normal: Tuple,
+}
+
+impl PartialEq for Triangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.material == other.material
+            && self.transform == other.transform
+            && self.p1 == other.p1
+            && self.p2 == other.p2
+            && self.p3 == other.p3
+    }
+}
+
+impl Triangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        let normal = e2.cross(e1).normalize();
+
+        Self {
+            id: next_shape_id(),
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
+            material: Material::default(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal,
+        }
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
+}
+
+/// Möller–Trumbore ray/triangle intersection in object space.
+///
+/// Returns `(t, u, v)` where `u` and `v` are the barycentric coordinates of the hit
+/// relative to `p2` and `p3`.
+pub(crate) fn intersect_triangle(
+    ray: &Ray,
+    p1: Tuple,
+    e1: Tuple,
+    e2: Tuple,
+) -> Option<(RtcFl, RtcFl, RtcFl)> {
+    let dir_cross_e2 = ray.direction.cross(e2);
+    let det = e1.dot(dir_cross_e2);
+
+    // The ray is parallel to the triangle
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = ray.origin - p1;
+    let u = f * p1_to_origin.dot(dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(e1);
+    let v = f * ray.direction.dot(origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = f * e2.dot(origin_cross_e1);
+    Some((t, u, v))
+}
+
+impl Shape for Triangle {
+    fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
+        let transformed_ray = ray.transform(*self.transform.inverse());
+
+        match intersect_triangle(&transformed_ray, self.p1, self.e1, self.e2) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => vec![],
+        }
+    }
+
+    fn normal_at(&self, _world_point: Tuple) -> Tuple {
+        self.normal_to_world(self.normal)
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        b.add_point(self.p1);
+        b.add_point(self.p2);
+        b.add_point(self.p3);
+        b
+    }
+
+    fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+}
+
+/// A triangle with a normal per vertex, interpolated across the face for smooth shading.
+#[derive(Debug, Clone)]
+pub struct SmoothTriangle {
+    pub id: usize,
+    transform: CachedTransform,
+    parent_transform: CachedTransform,
+    pub material: Material,
+    pub p1: Tuple,
+    pub p2: Tuple,
+    pub p3: Tuple,
+    pub n1: Tuple,
+    pub n2: Tuple,
+    pub n3: Tuple,
+    pub e1: Tuple,
+    pub e2: Tuple,
+}
+
+impl PartialEq for SmoothTriangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.material == other.material
+            && self.transform == other.transform
+            && self.p1 == other.p1
+            && self.p2 == other.p2
+            && self.p3 == other.p3
+            && self.n1 == other.n1
+            && self.n2 == other.n2
+            && self.n3 == other.n3
+    }
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Tuple, p2: Tuple, p3: Tuple, n1: Tuple, n2: Tuple, n3: Tuple) -> Self {
+        Self {
+            id: next_shape_id(),
+            transform: CachedTransform::default(),
+            parent_transform: CachedTransform::default(),
+            material: Material::default(),
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+
+    /// Panics if `transform` can't be inverted; use `try_set_transform` to check first.
+    pub fn set_transform(&mut self, transform: Matrix4) {
+        self.transform = CachedTransform::new(transform);
+    }
+
+    pub fn try_set_transform(&mut self, transform: Matrix4) -> Result<(), NotInvertible> {
+        self.transform = CachedTransform::try_new(transform)?;
+        Ok(())
+    }
+
+    fn interpolate_normal(&self, u: RtcFl, v: RtcFl) -> Tuple {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn intersect<'s>(&'s self, ray: &Ray) -> Vec<Intersection<'s>> {
+        let transformed_ray = ray.transform(*self.transform.inverse());
+
+        match intersect_triangle(&transformed_ray, self.p1, self.e1, self.e2) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => vec![],
+        }
+    }
+
+    fn normal_at(&self, world_point: Tuple) -> Tuple {
+        // Without a hit to go on, work out the barycentric coordinates from the point itself
+        let object_point = self.world_to_object(world_point);
+
+        let p = object_point - self.p1;
+        let d00 = self.e1.dot(self.e1);
+        let d01 = self.e1.dot(self.e2);
+        let d11 = self.e2.dot(self.e2);
+        let d20 = p.dot(self.e1);
+        let d21 = p.dot(self.e2);
+        let denom = d00 * d11 - d01 * d01;
+
+        let u = (d11 * d20 - d01 * d21) / denom;
+        let v = (d00 * d21 - d01 * d20) / denom;
+
+        self.normal_to_world(self.interpolate_normal(u, v))
+    }
+
+    fn normal_at_hit(&self, _world_point: Tuple, hit: &Intersection) -> Tuple {
+        self.normal_to_world(self.interpolate_normal(hit.u, hit.v))
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let mut b = BoundingBox::empty();
+        b.add_point(self.p1);
+        b.add_point(self.p2);
+        b.add_point(self.p3);
+        b
+    }
+
+    fn transform(&self) -> &CachedTransform {
+        &self.transform
+    }
+
+    fn parent_transform(&self) -> &CachedTransform {
+        &self.parent_transform
+    }
+
+    fn set_parent_transform(&mut self, transform: Matrix4) {
+        self.parent_transform = CachedTransform::new(transform);
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn clone_boxed(&self) -> Box<dyn Shape> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        computation::Computation,
+        intersections::{Intersection, Intersections},
+        ray::Ray,
+        shape::Shape,
+        triangle::{SmoothTriangle, Triangle},
+        tuples::{point, vector},
+        util::equal,
+    };
+
+    fn smooth_triangle_for_test() -> SmoothTriangle {
+        SmoothTriangle::new(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+            vector(0.0, 1.0, 0.0),
+            vector(-1.0, 0.0, 0.0),
+            vector(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn constructing_a_triangle() {
+        let p1 = point(0.0, 1.0, 0.0);
+        let p2 = point(-1.0, 0.0, 0.0);
+        let p3 = point(1.0, 0.0, 0.0);
+        let t = Triangle::new(p1, p2, p3);
+
+        assert_eq!(t.p1, p1);
+        assert_eq!(t.p2, p2);
+        assert_eq!(t.p3, p3);
+        assert_eq!(t.e1, vector(-1.0, -1.0, 0.0));
+        assert_eq!(t.e2, vector(1.0, -1.0, 0.0));
+        assert_eq!(t.normal, vector(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_triangle() {
+        let t = Triangle::new(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+        );
+
+        assert_eq!(t.normal_at(point(0.0, 0.5, 0.0)), t.normal);
+        assert_eq!(t.normal_at(point(-0.5, 0.75, 0.0)), t.normal);
+        assert_eq!(t.normal_at(point(0.5, 0.25, 0.0)), t.normal);
+    }
+
+    #[test]
+    fn intersecting_a_ray_parallel_to_the_triangle() {
+        let t = Triangle::new(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+        );
+        let r = Ray::new(&point(0.0, -1.0, -2.0), &vector(0.0, 1.0, 0.0));
+
+        assert!(t.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_misses_the_triangle_edges() {
+        let t = Triangle::new(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+        );
+        let origins = [
+            point(1.0, 1.0, -2.0),
+            point(-1.0, 1.0, -2.0),
+            point(0.0, -1.0, -2.0),
+        ];
+
+        for origin in origins {
+            let r = Ray::new(&origin, &vector(0.0, 0.0, 1.0));
+            assert!(t.intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn ray_strikes_a_triangle() {
+        let t = Triangle::new(
+            point(0.0, 1.0, 0.0),
+            point(-1.0, 0.0, 0.0),
+            point(1.0, 0.0, 0.0),
+        );
+        let r = Ray::new(&point(0.0, 0.5, -2.0), &vector(0.0, 0.0, 1.0));
+
+        let xs = t.intersect(&r);
+
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].t, 2.0);
+    }
+
+    #[test]
+    fn intersection_with_smooth_triangle_stores_u_and_v() {
+        let tri = smooth_triangle_for_test();
+        let r = Ray::new(&point(-0.2, 0.3, -2.0), &vector(0.0, 0.0, 1.0));
+
+        let xs = tri.intersect(&r);
+
+        assert!(equal(xs[0].u, 0.45));
+        assert!(equal(xs[0].v, 0.25));
+    }
+
+    #[test]
+    fn smooth_triangle_uses_u_and_v_to_interpolate_the_normal() {
+        let tri = smooth_triangle_for_test();
+        let i = Intersection::with_uv(1.0, &tri, 0.45, 0.25);
+
+        let n = tri.normal_at_hit(point(0.0, 0.0, 0.0), &i);
+
+        assert_eq!(n, vector(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn smooth_triangle_normal_without_a_hit_matches_the_interpolated_one() {
+        let tri = smooth_triangle_for_test();
+
+        let n = tri.normal_at(point(-0.2, 0.3, 0.0));
+
+        assert_eq!(n, vector(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn preparing_the_normal_on_a_smooth_triangle() {
+        let tri = smooth_triangle_for_test();
+        let i = Intersection::with_uv(1.0, &tri, 0.45, 0.25);
+        let r = Ray::new(&point(-0.2, 0.3, -2.0), &vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![i]);
+
+        let comps = Computation::new(xs.hit().unwrap(), &r, &xs);
+
+        assert_eq!(comps.normalv, vector(-0.5547, 0.83205, 0.0));
+    }
+
+    #[test]
+    fn a_triangle_has_a_bounding_box() {
+        let t = Triangle::new(
+            point(-3.0, 7.0, 2.0),
+            point(6.0, 2.0, -4.0),
+            point(2.0, -1.0, -1.0),
+        );
+
+        let b = t.bounds();
+
+        assert_eq!(b.min, point(-3.0, -1.0, -4.0));
+        assert_eq!(b.max, point(6.0, 7.0, 2.0));
+    }
+}
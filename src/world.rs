@@ -1,17 +1,40 @@
+use crate::bvh::{Bvh, BvhSplit};
 use crate::color::Color;
 use crate::computation::Computation;
 use crate::intersections::{Intersection, Intersections};
-use crate::light::{lighting, Light};
+use crate::light::{lighting, Light, LightSample};
+use crate::material::Material;
 use crate::ray::Ray;
 use crate::shape::Shape;
 use crate::sphere::Sphere;
 use crate::transformation::scaling;
 use crate::tuples::{point, Tuple};
+use crate::util::{RtcFl, EPSILON};
+
+/// How many times a ray may bounce between reflective surfaces unless told otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 5;
 
-#[derive(Default)]
 pub struct World {
-    pub objects: Vec<Box<dyn Shape>>,
+    objects: Vec<Box<dyn Shape>>,
     pub light: Vec<Light>,
+    /// The number of bounces `color_at` allows before giving up on secondary rays.
+    pub max_depth: usize,
+    /// The split the renderer builds a BVH with when the world doesn't have one yet. `None`
+    /// renders without one.
+    pub auto_bvh: Option<BvhSplit>,
+    bvh: Option<Bvh>,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self {
+            objects: vec![],
+            light: vec![],
+            max_depth: DEFAULT_MAX_DEPTH,
+            auto_bvh: Some(BvhSplit::default()),
+            bvh: None,
+        }
+    }
 }
 
 impl Clone for World {
@@ -19,23 +42,65 @@ impl Clone for World {
         Self {
             objects: self.objects.iter().map(|obj| obj.clone_boxed()).collect(),
             light: self.light.clone(),
+            max_depth: self.max_depth,
+            auto_bvh: self.auto_bvh,
+            bvh: self.bvh.clone(),
         }
     }
 }
 
 impl World {
     pub fn intersect<'w>(&'w self, ray: &Ray) -> Intersections<'w> {
-        let xs: Vec<Intersection<'w>> = self
-            .objects
-            .iter()
-            .flat_map(|shape_in_box| shape_in_box.intersect(ray))
-            .collect();
+        let xs: Vec<Intersection<'w>> = match self.bvh() {
+            Some(bvh) => bvh.intersect(&self.objects, ray),
+            None => self
+                .objects
+                .iter()
+                .flat_map(|shape_in_box| shape_in_box.intersect(ray))
+                .collect(),
+        };
 
         Intersections::new(xs)
     }
 
+    /// Builds a BVH over the objects, and inside every group among them, so that rays are
+    /// only tested against shapes whose bounding boxes they pass through.
+    ///
+    /// Changing the objects drops the BVH again, so call this once the scene is complete.
+    pub fn build_bvh(&mut self, split: BvhSplit) {
+        for obj in self.objects.iter_mut() {
+            obj.build_bvh(split);
+        }
+        self.bvh = Some(Bvh::build(&self.objects, split));
+    }
+
+    /// Builds a BVH with `auto_bvh` unless the world already has one.
+    pub fn ensure_bvh(&mut self) {
+        if self.bvh.is_none() {
+            if let Some(split) = self.auto_bvh {
+                self.build_bvh(split);
+            }
+        }
+    }
+
+    fn bvh(&self) -> Option<&Bvh> {
+        self.bvh.as_ref()
+    }
+
+    pub fn objects(&self) -> &[Box<dyn Shape>] {
+        &self.objects
+    }
+
+    /// The objects, for replacing, removing or changing them. Drops the BVH, since any of that
+    /// can leave it out of date.
+    pub fn objects_mut(&mut self) -> &mut Vec<Box<dyn Shape>> {
+        self.bvh = None;
+        &mut self.objects
+    }
+
     pub fn add_object(&mut self, shape: impl Shape + 'static) {
         self.objects.push(Box::new(shape));
+        self.bvh = None;
     }
 
     pub fn add_objects(&mut self, shapes: Vec<impl Shape + 'static>) {
@@ -44,40 +109,122 @@ impl World {
         }
     }
 
-    pub fn shade_hit(&self, comps: Computation) -> Color {
-        lighting(
-            comps.shape.material(),
-            comps.shape.as_ref(),
-            &self.light[0],
-            &comps.point,
-            &comps.eyev,
-            &comps.normalv,
-            self.is_shadowed(&comps.over_point),
-        )
+    pub fn add_boxed_objects(&mut self, shapes: Vec<Box<dyn Shape>>) {
+        self.objects.extend(shapes);
+        self.bvh = None;
+    }
+
+    /// The color at a hit, including whatever it reflects and whatever shows through it.
+    ///
+    /// `remaining` is how many more bounces a reflected or refracted ray is allowed to take.
+    /// Every light adds its own share, so a world without lights shades everything black.
+    pub fn shade_hit(&self, comps: Computation, remaining: usize) -> Color {
+        // Each light contributes on its own, so one can be blocked while another still reaches
+        let surface = self.light.iter().fold(Color::black(), |color, light| {
+            // Jitter once here so the shadow test and the shading see the same sample points
+            let light = light.with_new_jitter();
+            color
+                + lighting(
+                    comps.shape.material(),
+                    comps.shape.as_ref(),
+                    &light,
+                    &comps.point,
+                    &comps.eyev,
+                    &comps.normalv,
+                    self.is_shadowed(&light, &comps.over_point),
+                )
+        });
+
+        let reflected = self.reflected_color(&comps, remaining);
+        let refracted = self.refracted_color(&comps, remaining);
+
+        let material = comps.shape.material();
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            // Glass-like surfaces reflect more at grazing angles and refract more head on
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
     }
 
-    pub fn color_at(&self, ray: &Ray) -> Color {
+    pub fn color_at(&self, ray: &Ray, remaining: usize) -> Color {
         let intersections = self.intersect(ray);
         match intersections.hit() {
             Some(hit) => {
-                let comps = Computation::new(hit, ray);
-                self.shade_hit(comps)
+                let comps = Computation::new(hit, ray, &intersections);
+                self.shade_hit(comps, remaining)
             }
             None => Color::black(),
         }
     }
 
-    pub fn is_shadowed(&self, point: &Tuple) -> bool {
-        let v = self.light[0].position - *point;
-        let distance = v.mag();
-        let direction = v.normalize();
+    pub fn reflected_color(&self, comps: &Computation, remaining: usize) -> Color {
+        let reflective = comps.shape.material().reflective;
 
-        let r = Ray::new(point, &direction);
-        let intersections = self.intersect(&r);
+        // Stop once we run out of bounces, so facing mirrors don't recurse forever
+        if remaining == 0 || reflective == 0.0 {
+            return Color::black();
+        }
+
+        // The book's offset. Starting from over_point, SHADOW_EPSILON above the surface, would
+        // move where the reflected ray lands
+        let origin = comps.point + comps.normalv * EPSILON;
+        let reflect_ray = Ray::new(&origin, &comps.reflectv);
+        self.color_at(&reflect_ray, remaining - 1) * reflective
+    }
+
+    pub fn refracted_color(&self, comps: &Computation, remaining: usize) -> Color {
+        let transparency = comps.shape.material().transparency;
+
+        if remaining == 0 || transparency == 0.0 {
+            return Color::black();
+        }
+
+        // Snell's law, with the angle of the incoming ray taken from the eye vector
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(comps.normalv);
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+
+        // Total internal reflection: nothing gets through
+        if sin2_t > 1.0 {
+            return Color::black();
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+        let refract_ray = Ray::new(&comps.under_point, &direction);
+
+        self.color_at(&refract_ray, remaining - 1) * transparency
+    }
 
+    /// How much of `light` reaches `point`: the share of its samples with nothing in the way.
+    ///
+    /// 1.0 means fully lit and 0.0 fully in shadow. Area lights land in between near the edges of
+    /// a shadow. Samples that carry no light, such as those beyond an attenuation cutoff, count as
+    /// visible without casting a ray; `lighting` already leaves them out, so they aren't
+    /// darkened twice.
+    pub fn is_shadowed(&self, light: &Light, point: &Tuple) -> RtcFl {
+        let visible = light
+            .samples_towards(*point)
+            .filter(|sample| sample.strength <= 0.0 || !self.is_blocked(point, sample))
+            .count();
+
+        visible as RtcFl / light.samples() as RtcFl
+    }
+
+    /// Whether something sits between `point` and the light `sample` came from.
+    fn is_blocked(&self, point: &Tuple, sample: &LightSample) -> bool {
+        let r = Ray::new(point, &sample.direction);
+
+        if let Some(bvh) = self.bvh() {
+            return bvh.hit_before(&self.objects, &r, sample.distance);
+        }
+
+        let intersections = self.intersect(&r);
         if let Some(hit) = intersections.hit() {
             // If the hit object is closer than the light source
-            hit.t < distance
+            hit.t < sample.distance
         } else {
             false
         }
@@ -85,15 +232,7 @@ impl World {
 }
 
 pub fn color_at(world: &World, ray: Ray) -> Color {
-    let intersections = world.intersect(&ray);
-
-    match intersections.hit() {
-        Some(hit) => {
-            let comps = Computation::new(hit, &ray);
-            world.shade_hit(comps)
-        }
-        None => Color::black(),
-    }
+    world.color_at(&ray, world.max_depth)
 }
 
 pub fn create_default_world_for_test() -> World {
@@ -104,10 +243,7 @@ pub fn create_default_world_for_test() -> World {
     s1_created.material.diffuse = 0.7;
     s1_created.material.specular = 0.2;
 
-    let s2_created = Sphere {
-        transform: scaling(0.5, 0.5, 0.5),
-        ..Default::default()
-    };
+    let s2_created = Sphere::new(scaling(0.5, 0.5, 0.5), Material::default());
 
     let mut world = World::default();
     world.add_object(s1_created);
@@ -119,17 +255,20 @@ pub fn create_default_world_for_test() -> World {
 
 #[cfg(test)]
 mod tests {
+    use crate::bvh::BvhSplit;
     use crate::color::Color;
     use crate::computation::Computation;
-    use crate::intersections::Intersection;
-    use crate::light::Light;
+    use crate::intersections::{Intersection, Intersections};
+    use crate::light::{lighting, Attenuation, Light};
     use crate::material::Material;
     use crate::matrix::{view_transform, Matrix4, Operations};
+    use crate::plane::Plane;
     use crate::ray::Ray;
-    use crate::sphere::Sphere;
+    use crate::shape::Shape;
+    use crate::sphere::{glass_sphere_for_test, Sphere};
     use crate::transformation::{scaling, translation, Transformation};
     use crate::tuples::{point, vector};
-    use crate::world::{create_default_world_for_test, World};
+    use crate::world::{create_default_world_for_test, World, DEFAULT_MAX_DEPTH};
 
     #[test]
     fn creating_a_world() {
@@ -148,22 +287,21 @@ mod tests {
         s1.material.diffuse = 0.7;
         s1.material.specular = 0.2;
 
-        let mut s2 = Sphere::default();
-        s2.transform = scaling(0.5, 0.5, 0.5);
+        let s2 = Sphere::new(scaling(0.5, 0.5, 0.5), Material::default());
 
         assert!(w.light.len() == 1);
-        assert!(w.light[0].intensity == Color::new(1.0, 1.0, 1.0));
-        assert!(w.light[0].position == point(-10.0, 10.0, -10.0));
+        assert!(w.light[0].intensity() == Color::new(1.0, 1.0, 1.0));
+        assert!(w.light[0].position() == Some(point(-10.0, 10.0, -10.0)));
 
         assert_eq!(w.objects.len(), 2);
         assert!(w
             .objects
             .iter()
-            .any(|shape| shape.material() == &s1.material && shape.transform() == &s1.transform));
+            .any(|shape| shape.material() == &s1.material && shape.transform() == s1.transform()));
         assert!(w
             .objects
             .iter()
-            .any(|shape| shape.material() == &s2.material && shape.transform() == &s2.transform));
+            .any(|shape| shape.material() == &s2.material && shape.transform() == s2.transform()));
     }
 
     #[test]
@@ -240,7 +378,7 @@ mod tests {
 
         let p = point(0.0, 10.0, 0.0);
 
-        assert_eq!(w.is_shadowed(&p), false);
+        assert_eq!(w.is_shadowed(&w.light[0], &p), 1.0);
     }
 
     #[test]
@@ -249,7 +387,7 @@ mod tests {
 
         let p = point(10.0, -10.0, 10.0);
 
-        assert_eq!(w.is_shadowed(&p), true);
+        assert_eq!(w.is_shadowed(&w.light[0], &p), 0.0);
     }
 
     #[test]
@@ -258,7 +396,7 @@ mod tests {
 
         let p = point(-20.0, 20.0, -20.0);
 
-        assert_eq!(w.is_shadowed(&p), false);
+        assert_eq!(w.is_shadowed(&w.light[0], &p), 1.0);
     }
 
     #[test]
@@ -267,7 +405,91 @@ mod tests {
 
         let p = point(-2.0, 2.0, -2.0);
 
-        assert_eq!(w.is_shadowed(&p), false);
+        assert_eq!(w.is_shadowed(&w.light[0], &p), 1.0);
+    }
+
+    #[test]
+    fn area_lights_are_partly_shadowed() {
+        let w = create_default_world_for_test();
+        let light = Light::area(
+            point(-0.5, -0.5, -5.0),
+            vector(1.0, 0.0, 0.0),
+            2,
+            vector(0.0, 1.0, 0.0),
+            2,
+            Color::white(),
+        );
+        let examples = [
+            (point(0.0, 0.0, 2.0), 0.0),
+            (point(1.0, -1.0, 2.0), 0.25),
+            (point(1.5, 0.0, 2.0), 0.5),
+            (point(1.25, 1.25, 3.0), 0.75),
+            (point(0.0, 0.0, -2.0), 1.0),
+        ];
+
+        for (p, expected) in examples {
+            assert_eq!(w.is_shadowed(&light, &p), expected, "at {:?}", p);
+        }
+    }
+
+    #[test]
+    fn directional_lights_cast_shadows_from_any_distance() {
+        let w = create_default_world_for_test();
+        let light = Light::directional(vector(0.0, -1.0, 0.0), Color::white());
+
+        assert_eq!(w.is_shadowed(&light, &point(0.0, -2.0, 0.0)), 0.0);
+        assert_eq!(w.is_shadowed(&light, &point(0.0, -1000.0, 0.0)), 0.0);
+        assert_eq!(w.is_shadowed(&light, &point(2.0, -2.0, 0.0)), 1.0);
+        assert_eq!(w.is_shadowed(&light, &point(0.0, 2.0, 0.0)), 1.0);
+    }
+
+    #[test]
+    fn a_light_beyond_its_cutoff_casts_no_light() {
+        let mut w = create_default_world_for_test();
+        w.light[0] = w.light[0].with_attenuation(Attenuation::default().with_cutoff(20.0));
+        let near = point(-2.0, 2.0, -2.0);
+        let far = point(10.0, 10.0, 10.0);
+
+        assert_eq!(w.is_shadowed(&w.light[0], &near), 1.0);
+        assert_eq!(w.is_shadowed(&w.light[0], &far), 1.0);
+        assert_eq!(w.light[0].attenuation_at(far), 0.0);
+
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let lit = w.color_at(&r, DEFAULT_MAX_DEPTH);
+        w.light[0] = w.light[0].with_attenuation(Attenuation::default().with_cutoff(5.0));
+
+        assert_eq!(lit, Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(w.color_at(&r, DEFAULT_MAX_DEPTH), Color::black());
+    }
+
+    #[test]
+    fn an_area_light_partly_beyond_its_cutoff_is_only_dimmed_once() {
+        let w = World::default();
+        // Two samples 1.5 from the origin and two 3.2 away, past the cutoff
+        let light = Light::area(
+            point(-1.0, 1.0, 0.0),
+            vector(2.0, 0.0, 0.0),
+            2,
+            vector(0.0, 0.0, 4.0),
+            2,
+            Color::white(),
+        )
+        .with_attenuation(Attenuation::default().with_cutoff(2.5));
+        let m = Material {
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.0,
+            ..Default::default()
+        };
+        let p = point(0.0, 0.0, 0.0);
+        let up = vector(0.0, 1.0, 0.0);
+
+        let visible = w.is_shadowed(&light, &p);
+        let c = lighting(&m, &Sphere::default(), &light, &p, &up, &up, visible);
+
+        assert_eq!(visible, 1.0);
+        // Ambient plus half of the four samples, each lighting at 2/3 of 0.9
+        assert_eq!(c, Color::new(0.4, 0.4, 0.4));
     }
 
     #[test]
@@ -282,9 +504,382 @@ mod tests {
 
         let r = Ray::new(&point(0.0, 0.0, 5.0), &vector(0.0, 0.0, 1.0));
         let i = Intersection::new(4.0, &s2);
-        let comps = Computation::new(i, &r);
-        let c = w.shade_hit(comps);
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
+        let c = w.shade_hit(comps, DEFAULT_MAX_DEPTH);
 
         assert!(c == Color::new(0.1, 0.1, 0.1));
     }
+
+    #[test]
+    fn shade_hit_sums_the_contribution_of_every_light() {
+        let mut w = create_default_world_for_test();
+        w.light = vec![
+            Light::point(point(-10.0, 10.0, -10.0), Color::new(1.0, 0.0, 0.0)),
+            Light::point(point(10.0, 10.0, -10.0), Color::new(0.0, 0.0, 1.0)),
+        ];
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let shape = w.objects[0].as_ref();
+        let i = Intersection::new(4.0, shape);
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
+        let c = w.shade_hit(comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(c, Color::new(0.38066, 0.0, 0.2855));
+    }
+
+    #[test]
+    fn shade_hit_tests_shadows_for_each_light() {
+        let mut w = World::default();
+        w.light
+            .push(Light::point(point(0.0, 0.0, -10.0), Color::white()));
+        w.light.push(Light::point(
+            point(0.0, 0.0, 5.0),
+            Color::new(0.0, 1.0, 0.0),
+        ));
+        let s1 = Sphere::default();
+        let s2 = Sphere::new(translation(0.0, 0.0, 10.0), Material::default());
+
+        w.add_objects(vec![s1.clone(), s2.clone()]);
+
+        let r = Ray::new(&point(0.0, 0.0, 5.0), &vector(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, &s2);
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
+        let c = w.shade_hit(comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(c, Color::new(0.1, 2.0, 0.1));
+    }
+
+    #[test]
+    fn a_world_without_lights_is_black() {
+        let mut w = create_default_world_for_test();
+        w.light.clear();
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        assert_eq!(w.color_at(&r, DEFAULT_MAX_DEPTH), Color::black());
+    }
+
+    #[test]
+    fn intersecting_a_world_through_its_bvh() {
+        let mut w = create_default_world_for_test();
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let expected: Vec<_> = w.intersect(&r).data.iter().map(|x| x.t).collect();
+
+        w.build_bvh(BvhSplit::Sah);
+        let xs = w.intersect(&r);
+
+        assert_eq!(xs.data.iter().map(|x| x.t).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn shadows_are_found_through_the_bvh() {
+        let mut w = create_default_world_for_test();
+        w.build_bvh(BvhSplit::Midpoint);
+
+        assert_eq!(w.is_shadowed(&w.light[0], &point(10.0, -10.0, 10.0)), 0.0);
+        assert_eq!(w.is_shadowed(&w.light[0], &point(-20.0, 20.0, -20.0)), 1.0);
+        assert_eq!(w.is_shadowed(&w.light[0], &point(-2.0, 2.0, -2.0)), 1.0);
+    }
+
+    #[test]
+    fn adding_an_object_after_building_the_bvh_still_finds_it() {
+        let mut w = create_default_world_for_test();
+        w.build_bvh(BvhSplit::Midpoint);
+
+        w.add_object(Sphere::new(
+            translation(0.0, 10.0, 0.0),
+            Material::default(),
+        ));
+        let r = Ray::new(&point(0.0, 10.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        assert_eq!(w.intersect(&r).data.len(), 2);
+    }
+
+    #[test]
+    fn replacing_an_object_after_building_the_bvh_still_finds_it() {
+        let mut w = create_default_world_for_test();
+        w.build_bvh(BvhSplit::Midpoint);
+
+        w.objects_mut()[1] = Box::new(Sphere::new(
+            translation(0.0, 10.0, 0.0),
+            Material::default(),
+        ));
+        let r = Ray::new(&point(0.0, 10.0, -5.0), &vector(0.0, 0.0, 1.0));
+
+        assert_eq!(w.intersect(&r).data.len(), 2);
+    }
+
+    #[test]
+    fn ensure_bvh_builds_only_when_asked_to() {
+        let mut w = create_default_world_for_test();
+        w.auto_bvh = None;
+        w.ensure_bvh();
+        assert!(w.bvh.is_none());
+
+        w.auto_bvh = Some(BvhSplit::Sah);
+        w.ensure_bvh();
+        assert!(w.bvh.is_some());
+    }
+
+    fn reflective_plane_for_test(reflective: f32, y: f32) -> Plane {
+        Plane::new(
+            translation(0.0, y, 0.0),
+            Material {
+                reflective,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn reflected_color_for_a_nonreflective_material() {
+        let w = create_default_world_for_test();
+        let r = Ray::new(&point(0.0, 0.0, 0.0), &vector(0.0, 0.0, 1.0));
+        let mut shape = Sphere::new(scaling(0.5, 0.5, 0.5), Material::default());
+        shape.material.ambient = 1.0;
+        let i = Intersection::new(1.0, &shape);
+
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
+        let color = w.reflected_color(&comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(color, Color::black());
+    }
+
+    #[test]
+    fn reflected_color_for_a_reflective_material() {
+        let mut w = create_default_world_for_test();
+        let shape = reflective_plane_for_test(0.5, -1.0);
+        w.add_object(shape.clone());
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        let r = Ray::new(
+            &point(0.0, 0.0, -3.0),
+            &vector(0.0, -half_sqrt2, half_sqrt2),
+        );
+        let i = Intersection::new((2.0_f32).sqrt(), &shape);
+
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
+        let color = w.reflected_color(&comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(color, Color::new(0.19032, 0.2379, 0.14274));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_material() {
+        let mut w = create_default_world_for_test();
+        let shape = reflective_plane_for_test(0.5, -1.0);
+        w.add_object(shape.clone());
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        let r = Ray::new(
+            &point(0.0, 0.0, -3.0),
+            &vector(0.0, -half_sqrt2, half_sqrt2),
+        );
+        let i = Intersection::new((2.0_f32).sqrt(), &shape);
+
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
+        let color = w.shade_hit(comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(color, Color::new(0.87677, 0.92436, 0.82918));
+    }
+
+    #[test]
+    fn color_at_with_mutually_reflective_surfaces() {
+        let mut w = World::default();
+        w.light
+            .push(Light::point(point(0.0, 0.0, 0.0), Color::white()));
+        w.add_object(reflective_plane_for_test(1.0, -1.0));
+        w.add_object(reflective_plane_for_test(1.0, 1.0));
+        let r = Ray::new(&point(0.0, 0.0, 0.0), &vector(0.0, 1.0, 0.0));
+
+        // Terminating at all is the point of this test
+        let color = w.color_at(&r, DEFAULT_MAX_DEPTH);
+
+        assert_ne!(color, Color::black());
+    }
+
+    #[test]
+    fn reflected_color_at_the_maximum_recursive_depth() {
+        let mut w = create_default_world_for_test();
+        let shape = reflective_plane_for_test(0.5, -1.0);
+        w.add_object(shape.clone());
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        let r = Ray::new(
+            &point(0.0, 0.0, -3.0),
+            &vector(0.0, -half_sqrt2, half_sqrt2),
+        );
+        let i = Intersection::new((2.0_f32).sqrt(), &shape);
+
+        let comps = Computation::new(i, &r, &Intersections::new(vec![i]));
+        let color = w.reflected_color(&comps, 0);
+
+        assert_eq!(color, Color::black());
+    }
+
+    // The spheres from the default world, for tests that need to change them first
+    fn default_spheres_for_test() -> (Sphere, Sphere) {
+        let mut s1 = Sphere::default();
+        s1.material.color = Color::new(0.8, 1.0, 0.6);
+        s1.material.diffuse = 0.7;
+        s1.material.specular = 0.2;
+
+        let s2 = Sphere::new(scaling(0.5, 0.5, 0.5), Material::default());
+
+        (s1, s2)
+    }
+
+    fn world_with_spheres(s1: Sphere, s2: Sphere) -> World {
+        let mut w = World::default();
+        w.light
+            .push(Light::point(point(-10.0, 10.0, -10.0), Color::white()));
+        w.add_object(s1);
+        w.add_object(s2);
+        w
+    }
+
+    #[test]
+    fn refracted_color_with_an_opaque_surface() {
+        let w = create_default_world_for_test();
+        let shape = w.objects[0].as_ref();
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(4.0, shape),
+            Intersection::new(6.0, shape),
+        ]);
+
+        let comps = Computation::new(xs.data[0], &r, &xs);
+        let c = w.refracted_color(&comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(c, Color::black());
+    }
+
+    #[test]
+    fn refracted_color_at_the_maximum_recursive_depth() {
+        let (mut s1, s2) = default_spheres_for_test();
+        s1.material.transparency = 1.0;
+        s1.material.refractive_index = 1.5;
+        let w = world_with_spheres(s1, s2);
+        let shape = w.objects[0].as_ref();
+        let r = Ray::new(&point(0.0, 0.0, -5.0), &vector(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(4.0, shape),
+            Intersection::new(6.0, shape),
+        ]);
+
+        let comps = Computation::new(xs.data[0], &r, &xs);
+        let c = w.refracted_color(&comps, 0);
+
+        assert_eq!(c, Color::black());
+    }
+
+    #[test]
+    fn refracted_color_under_total_internal_reflection() {
+        let (mut s1, s2) = default_spheres_for_test();
+        s1.material.transparency = 1.0;
+        s1.material.refractive_index = 1.5;
+        let w = world_with_spheres(s1, s2);
+        let shape = w.objects[0].as_ref();
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        let r = Ray::new(&point(0.0, 0.0, half_sqrt2), &vector(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(-half_sqrt2, shape),
+            Intersection::new(half_sqrt2, shape),
+        ]);
+
+        // We're inside the sphere, so look at the second intersection
+        let comps = Computation::new(xs.data[1], &r, &xs);
+        let c = w.refracted_color(&comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(c, Color::black());
+    }
+
+    #[test]
+    fn refracted_color_with_a_refracted_ray() {
+        // The outer sphere glows a flat white, which is all the refracted ray can end up seeing
+        let (mut s1, mut s2) = default_spheres_for_test();
+        s1.material.color = Color::white();
+        s1.material.ambient = 1.0;
+        s1.material.diffuse = 0.0;
+        s1.material.specular = 0.0;
+        s2.material = glass_sphere_for_test().material;
+        let w = world_with_spheres(s1, s2);
+        let (a, b) = (w.objects[0].as_ref(), w.objects[1].as_ref());
+        let r = Ray::new(&point(0.0, 0.0, 0.1), &vector(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(-0.9899, a),
+            Intersection::new(-0.4899, b),
+            Intersection::new(0.4899, b),
+            Intersection::new(0.9899, a),
+        ]);
+
+        let comps = Computation::new(xs.data[2], &r, &xs);
+        let c = w.refracted_color(&comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(c, Color::white());
+    }
+
+    #[test]
+    fn shade_hit_with_a_transparent_material() {
+        let mut w = create_default_world_for_test();
+        let floor = Plane::new(
+            translation(0.0, -1.0, 0.0),
+            Material {
+                transparency: 0.5,
+                refractive_index: 1.5,
+                ..Default::default()
+            },
+        );
+        let ball = Sphere::new(
+            translation(0.0, -3.5, -0.5),
+            Material {
+                color: Color::new(1.0, 0.0, 0.0),
+                ambient: 0.5,
+                ..Default::default()
+            },
+        );
+        w.add_object(floor.clone());
+        w.add_object(ball);
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        let r = Ray::new(
+            &point(0.0, 0.0, -3.0),
+            &vector(0.0, -half_sqrt2, half_sqrt2),
+        );
+        let xs = Intersections::new(vec![Intersection::new((2.0_f32).sqrt(), &floor)]);
+
+        let comps = Computation::new(xs.data[0], &r, &xs);
+        let color = w.shade_hit(comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(color, Color::new(0.93642, 0.68642, 0.68642));
+    }
+
+    #[test]
+    fn shade_hit_with_a_reflective_transparent_material() {
+        let mut w = create_default_world_for_test();
+        let floor = Plane::new(
+            translation(0.0, -1.0, 0.0),
+            Material {
+                reflective: 0.5,
+                transparency: 0.5,
+                refractive_index: 1.5,
+                ..Default::default()
+            },
+        );
+        let ball = Sphere::new(
+            translation(0.0, -3.5, -0.5),
+            Material {
+                color: Color::new(1.0, 0.0, 0.0),
+                ambient: 0.5,
+                ..Default::default()
+            },
+        );
+        w.add_object(floor.clone());
+        w.add_object(ball);
+        let half_sqrt2 = (2.0_f32).sqrt() / 2.0;
+        let r = Ray::new(
+            &point(0.0, 0.0, -3.0),
+            &vector(0.0, -half_sqrt2, half_sqrt2),
+        );
+        let xs = Intersections::new(vec![Intersection::new((2.0_f32).sqrt(), &floor)]);
+
+        let comps = Computation::new(xs.data[0], &r, &xs);
+        let color = w.shade_hit(comps, DEFAULT_MAX_DEPTH);
+
+        assert_eq!(color, Color::new(0.93391, 0.69643, 0.69243));
+    }
 }